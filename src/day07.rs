@@ -80,7 +80,7 @@ impl Day {
                 if let Some(step) = next_steps.pop_front() {
                     active_workers += 1;
                     todo.entry(second + step_len(step))
-                        .or_insert_with(HashSet::new)
+                        .or_default()
                         .insert(step);
                 }
             }
@@ -3,6 +3,7 @@ extern crate text_io;
 
 use text_io::scan;
 
+use std::cmp::Reverse;
 use std::collections::VecDeque;
 
 pub fn part01<T: AsRef<str>>(lines: &[T]) -> usize {
@@ -13,10 +14,121 @@ pub fn part02<T: AsRef<str>>(lines: &[T]) -> usize {
     Day::read_from(lines).part02()
 }
 
+/// Highest score when playing by `rules` instead of the puzzle ones
+pub fn winning_score_with<T: AsRef<str>>(lines: &[T], rules: MarbleRules) -> usize {
+    let mut day = Day::read_from(lines);
+    day.rules = rules;
+    day.find_winning_score(day.marble)
+}
+
+pub fn scoring_events<T: AsRef<str>>(lines: &[T]) -> Vec<ScoringEvent> {
+    let day = Day::read_from(lines);
+    let mut game = day.game::<RingCircle>(day.marble);
+    let mut events = vec![];
+    while game.marble < day.marble {
+        events.extend(game.turn());
+    }
+    events
+}
+
+pub fn leaderboard<T: AsRef<str>>(lines: &[T]) -> Vec<(usize, usize)> {
+    let day = Day::read_from(lines);
+    let mut game = day.game::<RingCircle>(day.marble);
+    game.play_until(day.marble);
+    game.leaderboard()
+}
+
+/// Marbles in the circle, starting from the lowest one, and the current marble right after
+/// marble number `marbles` has been played
+pub fn circle_at<T: AsRef<str>>(lines: &[T], marbles: usize) -> (Vec<usize>, usize) {
+    let day = Day::read_from(lines);
+    let mut game = day.game::<RingCircle>(marbles);
+    game.play_until(marbles);
+    (game.circle.marbles(), game.circle.current())
+}
+
 #[derive(Debug, Default)]
 struct Day {
     players: usize,
     marble: usize,
+    engine: Engine,
+    rules: MarbleRules,
+}
+
+/// How marbles are placed in and taken out of the circle
+#[derive(Clone, Debug, PartialEq)]
+pub struct MarbleRules {
+    /// Marbles multiple of this one are kept instead of placed
+    pub special_multiple: usize,
+    /// Marbles counter-clockwise from the current one to take out when keeping a marble
+    pub removal_offset: usize,
+    /// Marbles clockwise from the current one to place a new marble after
+    pub insertion_offset: usize,
+    /// Marbles already in the circle, clockwise from the current one
+    pub starting_circle: Vec<usize>,
+}
+
+impl Default for MarbleRules {
+    fn default() -> Self {
+        MarbleRules {
+            special_multiple: 23,
+            removal_offset: 7,
+            insertion_offset: 1,
+            starting_circle: vec![0],
+        }
+    }
+}
+
+/// Ways of keeping the circle of marbles, all yielding the same scores
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum Engine {
+    Deque,
+    #[default]
+    Ring,
+}
+
+/// Points a player scored by keeping a marble and taking another one out of the circle
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ScoringEvent {
+    pub marble: usize,
+    pub player: usize,
+    pub points: usize,
+    pub removed: usize,
+}
+
+struct Game<C> {
+    circle: C,
+    rules: MarbleRules,
+    scores: Vec<usize>,
+    marble: usize,
+    first_marble: usize,
+}
+
+/// Circle of marbles with a current marble, rotating clockwise for positive steps
+trait Circle {
+    /// Circle made of `marbles` in clockwise order, the first one being current
+    fn new(marbles: &[usize], capacity: usize) -> Self;
+    fn rotate(&mut self, steps: isize);
+    /// Places `marble` clockwise of the current one and makes it current
+    fn insert(&mut self, marble: usize);
+    /// Takes out the current marble, making the one clockwise of it current
+    fn remove(&mut self) -> usize;
+    fn current(&self) -> usize;
+    /// Marbles in clockwise order starting from the lowest one
+    fn marbles(&self) -> Vec<usize>;
+}
+
+/// Circle kept in a deque whose back holds the current marble
+struct DequeCircle {
+    marbles: VecDeque<usize>,
+}
+
+/// Circle kept as a doubly linked ring whose links are indexed by marble number
+struct RingCircle {
+    next: Vec<usize>,
+    prev: Vec<usize>,
+    current: usize,
+    len: usize,
 }
 
 impl Day {
@@ -37,19 +149,187 @@ impl Day {
     }
 
     fn find_winning_score(&self, marbles: usize) -> usize {
-        let mut scores = vec![0; self.players];
-        let mut circle: VecDeque<_> = [0].into();
-        for marble in 1..=marbles {
-            if marble % 23 == 0 {
-                circle.rotate_right(7);
-                scores[marble % self.players] += marble + circle.pop_back().expect("❌");
-                circle.rotate_left(1);
+        match self.engine {
+            Engine::Deque => self.winning_score::<DequeCircle>(marbles),
+            Engine::Ring => self.winning_score::<RingCircle>(marbles),
+        }
+    }
+
+    fn winning_score<C: Circle>(&self, marbles: usize) -> usize {
+        let mut game = self.game::<C>(marbles);
+        game.play_until(marbles);
+        game.scores.iter().cloned().max().expect("❌")
+    }
+
+    fn game<C: Circle>(&self, marbles: usize) -> Game<C> {
+        let starting_circle = &self.rules.starting_circle;
+        let last_marble = *starting_circle.iter().max().expect("❌");
+        assert!(self.rules.special_multiple > 0, "❌");
+        Game {
+            circle: C::new(starting_circle, marbles.max(last_marble) + 1),
+            rules: self.rules.clone(),
+            scores: vec![0; self.players],
+            marble: last_marble,
+            first_marble: last_marble + 1,
+        }
+    }
+}
+
+impl<C: Circle> Game<C> {
+    /// Plays the next marble, returning what was scored if anything
+    fn turn(&mut self) -> Option<ScoringEvent> {
+        self.marble += 1;
+        let marble = self.marble;
+        if marble.is_multiple_of(self.rules.special_multiple) {
+            self.circle.rotate(-(self.rules.removal_offset as isize));
+            let removed = self.circle.remove();
+            let player = (marble - self.first_marble) % self.scores.len() + 1;
+            self.scores[player - 1] += marble + removed;
+            Some(ScoringEvent {
+                marble,
+                player,
+                points: marble + removed,
+                removed,
+            })
+        } else {
+            self.circle.rotate(self.rules.insertion_offset as isize);
+            self.circle.insert(marble);
+            None
+        }
+    }
+
+    fn play_until(&mut self, marble: usize) {
+        while self.marble < marble {
+            self.turn();
+        }
+    }
+
+    /// Players and their scores, best first
+    fn leaderboard(&self) -> Vec<(usize, usize)> {
+        let mut leaderboard: Vec<_> = (1..).zip(self.scores.iter().copied()).collect();
+        leaderboard.sort_by_key(|&(player, score)| (Reverse(score), player));
+        leaderboard
+    }
+}
+
+impl Circle for DequeCircle {
+    fn new(marbles: &[usize], capacity: usize) -> Self {
+        let mut circle = VecDeque::with_capacity(capacity);
+        circle.extend(marbles.iter().skip(1));
+        circle.extend(marbles.first());
+        DequeCircle { marbles: circle }
+    }
+
+    fn rotate(&mut self, steps: isize) {
+        if !self.marbles.is_empty() {
+            let steps = steps.rem_euclid(self.marbles.len() as isize) as usize;
+            self.marbles.rotate_left(steps);
+        }
+    }
+
+    fn insert(&mut self, marble: usize) {
+        self.marbles.push_back(marble);
+    }
+
+    fn remove(&mut self) -> usize {
+        let marble = self.marbles.pop_back().expect("❌");
+        if !self.marbles.is_empty() {
+            self.marbles.rotate_left(1);
+        }
+        marble
+    }
+
+    fn current(&self) -> usize {
+        *self.marbles.back().expect("❌")
+    }
+
+    fn marbles(&self) -> Vec<usize> {
+        let lowest = (0..self.marbles.len())
+            .min_by_key(|&i| self.marbles[i])
+            .unwrap_or(0);
+        let mut marbles: Vec<_> = self.marbles.iter().copied().collect();
+        marbles.rotate_left(lowest);
+        marbles
+    }
+}
+
+impl Circle for RingCircle {
+    fn new(marbles: &[usize], capacity: usize) -> Self {
+        let mut circle = RingCircle {
+            next: vec![0; capacity],
+            prev: vec![0; capacity],
+            current: 0,
+            len: 0,
+        };
+        for &marble in marbles {
+            circle.insert(marble);
+        }
+        circle.rotate(1);
+        circle
+    }
+
+    fn rotate(&mut self, steps: isize) {
+        for _ in 0..steps.abs() {
+            self.current = if steps > 0 {
+                self.next[self.current]
             } else {
-                circle.rotate_left(1);
-                circle.push_back(marble);
-            }
+                self.prev[self.current]
+            };
         }
-        scores.iter().cloned().max().expect("❌")
+    }
+
+    fn insert(&mut self, marble: usize) {
+        if marble >= self.next.len() {
+            self.next.resize(marble + 1, 0);
+            self.prev.resize(marble + 1, 0);
+        }
+        self.len += 1;
+        if self.len == 1 {
+            self.next[marble] = marble;
+            self.prev[marble] = marble;
+            self.current = marble;
+            return;
+        }
+        let next = self.next[self.current];
+        self.next[self.current] = marble;
+        self.prev[next] = marble;
+        self.next[marble] = next;
+        self.prev[marble] = self.current;
+        self.current = marble;
+    }
+
+    fn remove(&mut self) -> usize {
+        assert!(self.len > 0, "❌");
+        self.len -= 1;
+        let marble = self.current;
+        let (prev, next) = (self.prev[marble], self.next[marble]);
+        self.next[prev] = next;
+        self.prev[next] = prev;
+        self.current = next;
+        marble
+    }
+
+    fn current(&self) -> usize {
+        self.current
+    }
+
+    fn marbles(&self) -> Vec<usize> {
+        if self.len == 0 {
+            return vec![];
+        }
+        let mut lowest = self.current;
+        let mut marble = self.next[self.current];
+        while marble != self.current {
+            lowest = lowest.min(marble);
+            marble = self.next[marble];
+        }
+        let mut marbles = vec![lowest];
+        let mut marble = self.next[lowest];
+        while marble != lowest {
+            marbles.push(marble);
+            marble = self.next[marble];
+        }
+        marbles
     }
 }
 
@@ -81,4 +361,171 @@ mod tests {
         test_part02_04: (part02, vec!["21 players; last marble is worth 6111 points"], 507583214),
         test_part02_05: (part02, vec!["30 players; last marble is worth 5807 points"], 320997431),
     }
+
+    macro_rules! test_engines {
+        ($($name:ident: $values:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (players, marbles) = $values;
+                    let mut day = Day { players, marble: marbles, ..Day::default() };
+                    day.engine = Engine::Deque;
+                    let deque_score = day.find_winning_score(marbles);
+                    day.engine = Engine::Ring;
+                    assert_eq!(day.find_winning_score(marbles), deque_score);
+                }
+            )*
+        }
+    }
+
+    test_engines! {
+        test_engines_01: (9, 25),
+        test_engines_02: (10, 1618),
+        test_engines_03: (1, 1000),
+        test_engines_04: (464, 71_730),
+        test_engines_05: (17, 110_400),
+    }
+
+    #[test]
+    fn test_circles() {
+        let expected = vec![
+            0, 16, 8, 17, 4, 18, 19, 2, 24, 20, 25, 10, 21, 5, 22, 11, 1, 12, 6, 13, 3, 14, 7, 15,
+        ];
+        let day = Day {
+            players: 9,
+            marble: 25,
+            ..Day::default()
+        };
+        let mut deque = day.game::<DequeCircle>(25);
+        deque.play_until(25);
+        assert_eq!(
+            (deque.circle.marbles(), deque.circle.current()),
+            (expected.clone(), 25)
+        );
+        let mut ring = day.game::<RingCircle>(25);
+        ring.play_until(25);
+        assert_eq!(
+            (ring.circle.marbles(), ring.circle.current()),
+            (expected, 25)
+        );
+    }
+
+    #[test]
+    fn test_circle_at() {
+        let lines = ["9 players; last marble is worth 25 points"];
+        assert_eq!(circle_at(&lines, 0), (vec![0], 0));
+        assert_eq!(circle_at(&lines, 1), (vec![0, 1], 1));
+        assert_eq!(circle_at(&lines, 4), (vec![0, 4, 2, 1, 3], 4));
+        assert_eq!(
+            circle_at(&lines, 22),
+            (
+                vec![
+                    0, 16, 8, 17, 4, 18, 9, 19, 2, 20, 10, 21, 5, 22, 11, 1, 12, 6, 13, 3, 14, 7,
+                    15
+                ],
+                22,
+            ),
+        );
+        assert_eq!(
+            circle_at(&lines, 23),
+            (
+                vec![0, 16, 8, 17, 4, 18, 19, 2, 20, 10, 21, 5, 22, 11, 1, 12, 6, 13, 3, 14, 7, 15],
+                19,
+            ),
+        );
+    }
+
+    #[test]
+    fn test_scoring_events() {
+        assert_eq!(
+            scoring_events(&["9 players; last marble is worth 25 points"]),
+            vec![ScoringEvent {
+                marble: 23,
+                player: 5,
+                points: 32,
+                removed: 9,
+            }],
+        );
+        let events = scoring_events(&["10 players; last marble is worth 1618 points"]);
+        assert_eq!(events.len(), 1618 / 23);
+        assert!(events
+            .iter()
+            .all(|e| e.marble % 23 == 0 && e.points == e.marble + e.removed));
+    }
+
+    #[test]
+    fn test_leaderboard() {
+        let board = leaderboard(&["9 players; last marble is worth 25 points"]);
+        assert_eq!(board[0], (5, 32));
+        assert_eq!(
+            board[1..],
+            [
+                (1, 0),
+                (2, 0),
+                (3, 0),
+                (4, 0),
+                (6, 0),
+                (7, 0),
+                (8, 0),
+                (9, 0)
+            ]
+        );
+        let board = leaderboard(&["10 players; last marble is worth 1618 points"]);
+        assert_eq!(board[0].1, 8317);
+        assert_eq!(board.len(), 10);
+    }
+
+    macro_rules! test_rules {
+        ($($name:ident: $values:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (players, marbles, rules, expected): (_, _, MarbleRules, _) = $values;
+                    let mut day = Day { players, marble: marbles, rules, ..Day::default() };
+                    let (score, circle, current) = expected;
+                    day.engine = Engine::Deque;
+                    assert_eq!(day.find_winning_score(marbles), score);
+                    let mut deque = day.game::<DequeCircle>(marbles);
+                    deque.play_until(marbles);
+                    assert_eq!((deque.circle.marbles(), deque.circle.current()), (circle.clone(), current));
+                    day.engine = Engine::Ring;
+                    assert_eq!(day.find_winning_score(marbles), score);
+                    let mut ring = day.game::<RingCircle>(marbles);
+                    ring.play_until(marbles);
+                    assert_eq!((ring.circle.marbles(), ring.circle.current()), (circle, current));
+                }
+            )*
+        }
+    }
+
+    test_rules! {
+        test_rules_01: (9, 25, MarbleRules::default(), (32, vec![
+            0, 16, 8, 17, 4, 18, 19, 2, 24, 20, 25, 10, 21, 5, 22, 11, 1, 12, 6, 13, 3, 14, 7, 15,
+        ], 25)),
+        test_rules_02: (3, 40, MarbleRules {
+            special_multiple: 5,
+            removal_offset: 2,
+            insertion_offset: 2,
+            starting_circle: vec![0, 1],
+        }, (104, vec![
+            1, 33, 23, 34, 7, 36, 24, 17, 37, 26, 4, 38, 27, 39, 18, 8, 28, 13, 29, 21, 31, 9, 14,
+            32, 22,
+        ], 27)),
+        test_rules_03: (4, 30, MarbleRules {
+            special_multiple: 3,
+            removal_offset: 1,
+            ..MarbleRules::default()
+        }, (77, vec![5, 25, 26, 17, 28, 29, 19, 20, 13, 22, 23], 29)),
+    }
+
+    #[test]
+    fn test_winning_score_with() {
+        let lines = ["10 players; last marble is worth 1618 points"];
+        assert_eq!(winning_score_with(&lines, MarbleRules::default()), 8317);
+        let rules = MarbleRules {
+            special_multiple: 19,
+            ..MarbleRules::default()
+        };
+        assert_eq!(winning_score_with(&lines, rules), 10862);
+    }
 }
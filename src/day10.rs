@@ -13,8 +13,74 @@ pub fn part02<T: AsRef<str>>(lines: &[T]) -> isize {
     Day::read_from(lines).part02()
 }
 
+/// Letters the points spell once they converge
+pub fn message<T: AsRef<str>>(lines: &[T]) -> Result<String, String> {
+    let mut day = Day::read_from(lines);
+    day.converge();
+    recognise(&day.draw())
+}
+
+const GLYPH_WIDTH: usize = 6;
+const GLYPH_HEIGHT: usize = 10;
+
+#[rustfmt::skip]
+const GLYPHS: [(char, [&str; GLYPH_HEIGHT]); 15] = [
+    ('A', ["  ##  ", " #  # ", "#    #", "#    #", "#    #", "######", "#    #", "#    #", "#    #", "#    #"]),
+    ('B', ["##### ", "#    #", "#    #", "#    #", "##### ", "#    #", "#    #", "#    #", "#    #", "##### "]),
+    ('C', [" #### ", "#    #", "#     ", "#     ", "#     ", "#     ", "#     ", "#     ", "#    #", " #### "]),
+    ('E', ["######", "#     ", "#     ", "#     ", "##### ", "#     ", "#     ", "#     ", "#     ", "######"]),
+    ('F', ["######", "#     ", "#     ", "#     ", "##### ", "#     ", "#     ", "#     ", "#     ", "#     "]),
+    ('G', [" #### ", "#    #", "#     ", "#     ", "#     ", "#  ###", "#    #", "#    #", "#   ##", " ### #"]),
+    ('H', ["#    #", "#    #", "#    #", "#    #", "######", "#    #", "#    #", "#    #", "#    #", "#    #"]),
+    ('J', ["   ###", "    # ", "    # ", "    # ", "    # ", "    # ", "    # ", "#   # ", "#   # ", " ###  "]),
+    ('K', ["#    #", "#   # ", "#  #  ", "# #   ", "##    ", "##    ", "# #   ", "#  #  ", "#   # ", "#    #"]),
+    ('L', ["#     ", "#     ", "#     ", "#     ", "#     ", "#     ", "#     ", "#     ", "#     ", "######"]),
+    ('N', ["#    #", "##   #", "##   #", "# #  #", "# #  #", "#  # #", "#  # #", "#   ##", "#   ##", "#    #"]),
+    ('P', ["##### ", "#    #", "#    #", "#    #", "##### ", "#     ", "#     ", "#     ", "#     ", "#     "]),
+    ('R', ["##### ", "#    #", "#    #", "#    #", "##### ", "#  #  ", "#   # ", "#   # ", "#    #", "#    #"]),
+    ('X', ["#    #", "#    #", " #  # ", " #  # ", "  ##  ", "  ##  ", " #  # ", " #  # ", "#    #", "#    #"]),
+    ('Z', ["######", "     #", "     #", "    # ", "   #  ", "  #   ", " #    ", "#     ", "#     ", "######"]),
+];
+
+/// How `Day::render` draws each frame
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum FrameFormat {
+    /// `#` for points and `.` for empty sky, like the puzzle text
+    #[default]
+    Text,
+    /// Plain (ASCII) portable bitmap, black where there is at least one point
+    Pbm,
+    /// Plain (ASCII) portable graymap, as bright as the number of points in the pixel
+    Pgm,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenderOptions {
+    /// Seconds rendered before and after the convergence
+    pub window: isize,
+    /// Side of the square of sky each pixel covers, so early frames stay small
+    pub scale: usize,
+    pub format: FrameFormat,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            window: 3,
+            scale: 1,
+            format: FrameFormat::Text,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    pub seconds: isize,
+    pub image: String,
+}
+
 #[derive(Debug, Default)]
-struct Day {
+pub struct Day {
     points: Vec<Point>,
     min_x: isize,
     max_x: isize,
@@ -31,7 +97,7 @@ struct Point {
 }
 
 impl Day {
-    fn read_from<T: AsRef<str>>(lines: &[T]) -> Self {
+    pub fn read_from<T: AsRef<str>>(lines: &[T]) -> Self {
         let mut day = Day {
             points: lines.iter().map(|l| parse_points(l.as_ref())).collect(),
             ..Default::default()
@@ -62,27 +128,123 @@ impl Day {
     }
 
     fn part01(&mut self) -> String {
-        let mut min_x_dist = isize::MAX;
-        while self.max_x - self.min_x <= min_x_dist {
-            min_x_dist = self.max_x - self.min_x;
-            self.step();
-            self.update_max_min();
-        }
-        self.step_back();
+        self.converge();
+        let drawing = self.draw();
+        recognise(&drawing).unwrap_or(drawing)
+    }
+
+    fn part02(&self) -> isize {
+        self.convergence_time()
+    }
+
+    /// Moves the points to the second they converge
+    fn converge(&mut self) {
+        self.advance(self.convergence_time());
         self.update_max_min();
-        self.draw()
     }
 
-    fn part02(&mut self) -> isize {
-        let mut seconds = 0;
-        let mut min_x_dist = isize::MAX;
-        while self.max_x - self.min_x <= min_x_dist {
-            min_x_dist = self.max_x - self.min_x;
-            self.step();
-            self.update_max_min();
+    /// Second at which the bounding box of the points is the smallest, estimated as the time
+    /// minimising the squared distances between every pair of points (which the pairs moving in
+    /// opposite directions dominate) and then refined one second at a time
+    fn convergence_time(&self) -> isize {
+        if self.points.is_empty() {
+            return 0;
+        }
+        let mut seconds = self.estimated_convergence_time().max(0);
+        while seconds > 0 && self.area_at(seconds - 1) < self.area_at(seconds) {
+            seconds -= 1;
+        }
+        while self.area_at(seconds + 1) < self.area_at(seconds) {
             seconds += 1;
         }
-        seconds - 1
+        seconds
+    }
+
+    /// Least-squares minimum of the spread of the points around their centroid
+    fn estimated_convergence_time(&self) -> isize {
+        let n = self.points.len() as i128;
+        let sum = |f: fn(&Point) -> isize| self.points.iter().map(|p| f(p) as i128).sum::<i128>();
+        let (sx, sy, svx, svy) = (sum(|p| p.x), sum(|p| p.y), sum(|p| p.vx), sum(|p| p.vy));
+        let (mut numerator, mut denominator) = (0, 0);
+        for p in &self.points {
+            let (dx, dy) = (p.x as i128 * n - sx, p.y as i128 * n - sy);
+            let (dvx, dvy) = (p.vx as i128 * n - svx, p.vy as i128 * n - svy);
+            numerator -= dx * dvx + dy * dvy;
+            denominator += dvx * dvx + dvy * dvy;
+        }
+        if denominator == 0 {
+            return 0;
+        }
+        let rounded = (2 * numerator + denominator).div_euclid(2 * denominator);
+        rounded.clamp(isize::MIN as i128, isize::MAX as i128) as isize
+    }
+
+    /// Area of the bounding box of the points after `seconds`
+    fn area_at(&self, seconds: isize) -> i128 {
+        let (mut min_x, mut max_x) = (i128::MAX, i128::MIN);
+        let (mut min_y, mut max_y) = (i128::MAX, i128::MIN);
+        for point in &self.points {
+            let x = point.x as i128 + point.vx as i128 * seconds as i128;
+            let y = point.y as i128 + point.vy as i128 * seconds as i128;
+            min_x = min_x.min(x);
+            max_x = max_x.max(x);
+            min_y = min_y.min(y);
+            max_y = max_y.max(y);
+        }
+        (max_x - min_x + 1) * (max_y - min_y + 1)
+    }
+
+    /// Frames of the sky around the second the points converge, each framed by its own bounding
+    /// box, skipping the seconds before the sky was first seen
+    pub fn render(&self, options: &RenderOptions) -> Vec<Frame> {
+        if self.points.is_empty() {
+            return vec![];
+        }
+        let seconds = self.convergence_time();
+        (seconds - options.window..=seconds + options.window)
+            .filter(|&s| s >= 0)
+            .map(|s| self.frame_at(s, options))
+            .collect()
+    }
+
+    fn frame_at(&self, seconds: isize, options: &RenderOptions) -> Frame {
+        let positions: Vec<_> = self.points.iter().map(|p| p.at(seconds)).collect();
+        let min_x = positions.iter().map(|&(x, _)| x).min().unwrap_or(0);
+        let max_x = positions.iter().map(|&(x, _)| x).max().unwrap_or(0);
+        let min_y = positions.iter().map(|&(_, y)| y).min().unwrap_or(0);
+        let max_y = positions.iter().map(|&(_, y)| y).max().unwrap_or(0);
+        let scale = options.scale.max(1) as isize;
+        let width = ((max_x - min_x) / scale + 1) as usize;
+        let height = ((max_y - min_y) / scale + 1) as usize;
+        let mut counts = vec![vec![0; width]; height];
+        for (x, y) in positions {
+            counts[((y - min_y) / scale) as usize][((x - min_x) / scale) as usize] += 1;
+        }
+        let mut image = match options.format {
+            FrameFormat::Text => String::new(),
+            FrameFormat::Pbm => format!("P1\n{} {}\n", width, height),
+            FrameFormat::Pgm => {
+                let max_count = counts.iter().flatten().max().unwrap_or(&0);
+                format!("P2\n{} {}\n{}\n", width, height, max_count)
+            }
+        };
+        for row in counts {
+            let pixels: Vec<String> = row
+                .iter()
+                .map(|&count| match options.format {
+                    FrameFormat::Text if count > 0 => "#".to_string(),
+                    FrameFormat::Text => ".".to_string(),
+                    FrameFormat::Pbm => usize::from(count > 0).to_string(),
+                    FrameFormat::Pgm => count.to_string(),
+                })
+                .collect();
+            match options.format {
+                FrameFormat::Text => image.push_str(&pixels.concat()),
+                _ => image.push_str(&pixels.join(" ")),
+            }
+            image.push('\n');
+        }
+        Frame { seconds, image }
     }
 
     fn draw(&self) -> String {
@@ -105,19 +267,86 @@ impl Day {
         s
     }
 
-    fn step(&mut self) {
+    fn advance(&mut self, seconds: isize) {
         for point in &mut self.points {
-            point.x += point.vx;
-            point.y += point.vy;
+            point.x += point.vx * seconds;
+            point.y += point.vy * seconds;
         }
     }
+}
 
-    fn step_back(&mut self) {
-        for point in &mut self.points {
-            point.x -= point.vx;
-            point.y -= point.vy;
+impl FrameFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            FrameFormat::Text => "txt",
+            FrameFormat::Pbm => "pbm",
+            FrameFormat::Pgm => "pgm",
+        }
+    }
+}
+
+impl Point {
+    fn at(&self, seconds: isize) -> (isize, isize) {
+        (self.x + self.vx * seconds, self.y + self.vy * seconds)
+    }
+}
+
+/// Single text file with every frame preceded by a marker line telling its second
+pub fn animation(frames: &[Frame]) -> String {
+    frames
+        .iter()
+        .map(|f| format!("--- {}s ---\n{}", f.seconds, f.image))
+        .collect()
+}
+
+/// Reads the letters off a drawing, splitting glyphs at the columns with no points
+fn recognise(drawing: &str) -> Result<String, String> {
+    let rows: Vec<Vec<char>> = drawing
+        .lines()
+        .skip(1)
+        .map(|l| l.chars().collect())
+        .collect();
+    if rows.len() != GLYPH_HEIGHT {
+        return Err(format!(
+            "message is {} rows tall, expected {}",
+            rows.len(),
+            GLYPH_HEIGHT
+        ));
+    }
+    let width = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    let is_blank = |x: usize| rows.iter().all(|r| r.get(x).is_none_or(|&c| c != '#'));
+    let mut message = String::new();
+    let mut x = 0;
+    while x < width {
+        if is_blank(x) {
+            x += 1;
+            continue;
+        }
+        let start = x;
+        while x < width && !is_blank(x) {
+            x += 1;
         }
+        let glyph: Vec<String> = rows
+            .iter()
+            .map(|r| {
+                let row: String = (start..x).map(|x| *r.get(x).unwrap_or(&' ')).collect();
+                format!("{:width$}", row, width = GLYPH_WIDTH)
+            })
+            .collect();
+        let letter = GLYPHS
+            .iter()
+            .find(|(_, g)| g.iter().zip(&glyph).all(|(a, b)| a == b))
+            .map(|&(c, _)| c)
+            .ok_or_else(|| {
+                format!(
+                    "unrecognised glyph at column {}:\n{}",
+                    start,
+                    glyph.join("\n")
+                )
+            })?;
+        message.push(letter);
     }
+    Ok(message)
 }
 
 fn parse_points(string: &str) -> Point {
@@ -136,6 +365,41 @@ fn parse_points(string: &str) -> Point {
 mod tests {
     use super::*;
 
+    #[rustfmt::skip]
+    const EXAMPLE: [&str; 31] = [
+        "position=< 9,  1> velocity=< 0,  2>",
+        "position=< 7,  0> velocity=<-1,  0>",
+        "position=< 3, -2> velocity=<-1,  1>",
+        "position=< 6, 10> velocity=<-2, -1>",
+        "position=< 2, -4> velocity=< 2,  2>",
+        "position=<-6, 10> velocity=< 2, -2>",
+        "position=< 1,  8> velocity=< 1, -1>",
+        "position=< 1,  7> velocity=< 1,  0>",
+        "position=<-3, 11> velocity=< 1, -2>",
+        "position=< 7,  6> velocity=<-1, -1>",
+        "position=<-2,  3> velocity=< 1,  0>",
+        "position=<-4,  3> velocity=< 2,  0>",
+        "position=<10, -3> velocity=<-1,  1>",
+        "position=< 5, 11> velocity=< 1, -2>",
+        "position=< 4,  7> velocity=< 0, -1>",
+        "position=< 8, -2> velocity=< 0,  1>",
+        "position=<15,  0> velocity=<-2,  0>",
+        "position=< 1,  6> velocity=< 1,  0>",
+        "position=< 8,  9> velocity=< 0, -1>",
+        "position=< 3,  3> velocity=<-1,  1>",
+        "position=< 0,  5> velocity=< 0, -1>",
+        "position=<-2,  2> velocity=< 2,  0>",
+        "position=< 5, -2> velocity=< 1,  2>",
+        "position=< 1,  4> velocity=< 2,  1>",
+        "position=<-2,  7> velocity=< 2, -2>",
+        "position=< 3,  6> velocity=<-1, -1>",
+        "position=< 5,  0> velocity=< 1,  0>",
+        "position=<-6,  0> velocity=< 2,  0>",
+        "position=< 5,  9> velocity=< 1, -2>",
+        "position=<14,  7> velocity=<-2,  0>",
+        "position=<-3,  6> velocity=< 2, -1>",
+    ];
+
     macro_rules! test_parts {
         ($($name:ident: $values:expr,)*) => {
             $(
@@ -150,39 +414,7 @@ mod tests {
 
     #[rustfmt::skip]
     test_parts! {
-        test_part01_01: (part01, vec![
-            "position=< 9,  1> velocity=< 0,  2>",
-            "position=< 7,  0> velocity=<-1,  0>",
-            "position=< 3, -2> velocity=<-1,  1>",
-            "position=< 6, 10> velocity=<-2, -1>",
-            "position=< 2, -4> velocity=< 2,  2>",
-            "position=<-6, 10> velocity=< 2, -2>",
-            "position=< 1,  8> velocity=< 1, -1>",
-            "position=< 1,  7> velocity=< 1,  0>",
-            "position=<-3, 11> velocity=< 1, -2>",
-            "position=< 7,  6> velocity=<-1, -1>",
-            "position=<-2,  3> velocity=< 1,  0>",
-            "position=<-4,  3> velocity=< 2,  0>",
-            "position=<10, -3> velocity=<-1,  1>",
-            "position=< 5, 11> velocity=< 1, -2>",
-            "position=< 4,  7> velocity=< 0, -1>",
-            "position=< 8, -2> velocity=< 0,  1>",
-            "position=<15,  0> velocity=<-2,  0>",
-            "position=< 1,  6> velocity=< 1,  0>",
-            "position=< 8,  9> velocity=< 0, -1>",
-            "position=< 3,  3> velocity=<-1,  1>",
-            "position=< 0,  5> velocity=< 0, -1>",
-            "position=<-2,  2> velocity=< 2,  0>",
-            "position=< 5, -2> velocity=< 1,  2>",
-            "position=< 1,  4> velocity=< 2,  1>",
-            "position=<-2,  7> velocity=< 2, -2>",
-            "position=< 3,  6> velocity=<-1, -1>",
-            "position=< 5,  0> velocity=< 1,  0>",
-            "position=<-6,  0> velocity=< 2,  0>",
-            "position=< 5,  9> velocity=< 1, -2>",
-            "position=<14,  7> velocity=<-2,  0>",
-            "position=<-3,  6> velocity=< 2, -1>",
-        ], "
+        test_part01_01: (part01, EXAMPLE.to_vec(), "
 #   #  ###\n\
 #   #   # \n\
 #   #   # \n\
@@ -192,38 +424,109 @@ mod tests {
 #   #   # \n\
 #   #  ###\n"),
 
-        test_part02_01: (part02, vec![
-            "position=< 9,  1> velocity=< 0,  2>",
-            "position=< 7,  0> velocity=<-1,  0>",
-            "position=< 3, -2> velocity=<-1,  1>",
-            "position=< 6, 10> velocity=<-2, -1>",
-            "position=< 2, -4> velocity=< 2,  2>",
-            "position=<-6, 10> velocity=< 2, -2>",
-            "position=< 1,  8> velocity=< 1, -1>",
-            "position=< 1,  7> velocity=< 1,  0>",
-            "position=<-3, 11> velocity=< 1, -2>",
-            "position=< 7,  6> velocity=<-1, -1>",
-            "position=<-2,  3> velocity=< 1,  0>",
-            "position=<-4,  3> velocity=< 2,  0>",
-            "position=<10, -3> velocity=<-1,  1>",
-            "position=< 5, 11> velocity=< 1, -2>",
-            "position=< 4,  7> velocity=< 0, -1>",
-            "position=< 8, -2> velocity=< 0,  1>",
-            "position=<15,  0> velocity=<-2,  0>",
-            "position=< 1,  6> velocity=< 1,  0>",
-            "position=< 8,  9> velocity=< 0, -1>",
-            "position=< 3,  3> velocity=<-1,  1>",
-            "position=< 0,  5> velocity=< 0, -1>",
-            "position=<-2,  2> velocity=< 2,  0>",
-            "position=< 5, -2> velocity=< 1,  2>",
-            "position=< 1,  4> velocity=< 2,  1>",
-            "position=<-2,  7> velocity=< 2, -2>",
-            "position=< 3,  6> velocity=<-1, -1>",
-            "position=< 5,  0> velocity=< 1,  0>",
-            "position=<-6,  0> velocity=< 2,  0>",
-            "position=< 5,  9> velocity=< 1, -2>",
-            "position=<14,  7> velocity=<-2,  0>",
-            "position=<-3,  6> velocity=< 2, -1>",
-        ], 3),
+        test_part02_01: (part02, EXAMPLE.to_vec(), 3),
+    }
+
+    #[test]
+    fn test_part01_input() {
+        assert_eq!(
+            part01(&crate::input::read_lines_from_input("data/day10")),
+            "KFLBHXGK"
+        );
+    }
+
+    #[test]
+    fn test_convergence_time() {
+        let mut day = Day::read_from(&EXAMPLE);
+        assert_eq!(day.estimated_convergence_time(), 3);
+        assert_eq!(day.convergence_time(), 3);
+        // The same sky a million seconds earlier
+        day.advance(-1_000_000);
+        assert_eq!(day.convergence_time(), 1_000_003);
+        day.advance(1_000_005);
+        assert_eq!(day.convergence_time(), 0);
+    }
+
+    #[test]
+    fn test_render() {
+        let day = Day::read_from(&EXAMPLE);
+        let frames = day.render(&RenderOptions {
+            window: 1,
+            ..RenderOptions::default()
+        });
+        assert_eq!(
+            frames.iter().map(|f| f.seconds).collect::<Vec<_>>(),
+            [2, 3, 4]
+        );
+        assert_eq!(
+            frames[1].image,
+            [
+                "#...#..###\n",
+                "#...#...#.\n",
+                "#...#...#.\n",
+                "#####...#.\n",
+                "#...#...#.\n",
+                "#...#...#.\n",
+                "#...#...#.\n",
+                "#...#..###\n",
+            ]
+            .concat(),
+        );
+        assert!(animation(&frames).starts_with("--- 2s ---\n"));
+        assert!(animation(&frames).contains("\n--- 3s ---\n#...#..###\n"));
+        assert_eq!(day.render(&RenderOptions::default())[0].seconds, 0);
+    }
+
+    #[test]
+    fn test_render_formats() {
+        let day = Day::read_from(&EXAMPLE);
+        let options = RenderOptions {
+            window: 0,
+            scale: 1,
+            format: FrameFormat::Pbm,
+        };
+        let image = &day.render(&options)[0].image;
+        assert!(image.starts_with("P1\n10 8\n1 0 0 0 1 0 0 1 1 1\n"));
+        let options = RenderOptions {
+            scale: 5,
+            format: FrameFormat::Pgm,
+            ..options
+        };
+        let image = &day.render(&options)[0].image;
+        assert!(image.starts_with("P2\n2 2\n"));
+        let pixels: Vec<usize> = image
+            .split_whitespace()
+            .skip(4)
+            .map(|p| p.parse().unwrap())
+            .collect();
+        assert_eq!(pixels.iter().sum::<usize>(), EXAMPLE.len());
+        assert_eq!(FrameFormat::Pgm.extension(), "pgm");
+    }
+
+    #[test]
+    fn test_recognise() {
+        let mut drawing = String::from("\n");
+        for row in 0..GLYPH_HEIGHT {
+            let glyphs: Vec<_> = GLYPHS.iter().map(|(_, g)| g[row]).collect();
+            drawing.push_str(&glyphs.join("  "));
+            drawing.push('\n');
+        }
+        assert_eq!(recognise(&drawing), Ok("ABCEFGHJKLNPRXZ".to_string()));
+    }
+
+    #[test]
+    fn test_recognise_errors() {
+        assert_eq!(
+            recognise("\n#\n#\n"),
+            Err("message is 2 rows tall, expected 10".to_string()),
+        );
+        let drawing = format!("\n{}", "#  #\n".repeat(GLYPH_HEIGHT));
+        assert_eq!(
+            recognise(&drawing),
+            Err(format!(
+                "unrecognised glyph at column 0:\n{}",
+                ["#     "; 10].join("\n")
+            )),
+        );
     }
 }
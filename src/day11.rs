@@ -1,6 +1,8 @@
 /// Day 11 (https://adventofcode.com/2018/day/11)
 extern crate text_io;
 
+use std::thread;
+
 const GRID_SIZE: usize = 300;
 const SQUARE_SIZE: usize = 3;
 
@@ -16,25 +18,117 @@ pub fn part02<T: AsRef<str>>(lines: &[T]) -> String {
 
 type Cell = (usize, usize);
 
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum HeatmapFormat {
+    /// Plain (ASCII) portable graymap, from black for -5 to white for 4
+    #[default]
+    Pgm,
+    Csv,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct HeatmapOptions {
+    pub format: HeatmapFormat,
+    /// Also lists the square with the largest total power for each size, as comments in PGM
+    /// images and as a second table in CSV files
+    pub best_totals: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct Day {
+    grid: Grid,
+}
+
+/// Fuel cells from (1, 1) to (`width`, `height`)
 #[derive(Debug, Default)]
-struct Day {
-    grid_serial_number: isize,
+struct Grid {
+    width: usize,
+    height: usize,
+    serial_number: isize,
+    /// Total power of the cells right of and below each cell, included, so the total power of
+    /// any square takes four lookups
     prefixed_power_levels: Vec<Vec<isize>>,
 }
 
 impl Day {
-    fn read_from<T: AsRef<str>>(lines: &[T]) -> Self {
-        let mut day = Day {
-            grid_serial_number: lines.first().expect("❌").as_ref().parse().expect("❌"),
-            prefixed_power_levels: vec![vec![0; GRID_SIZE + 2]; GRID_SIZE + 2],
+    pub fn read_from<T: AsRef<str>>(lines: &[T]) -> Self {
+        let serial_number = lines.first().expect("❌").as_ref().parse().expect("❌");
+        Day {
+            grid: Grid::new(GRID_SIZE, GRID_SIZE, serial_number),
+        }
+    }
+
+    fn part01(&self) -> Cell {
+        self.grid
+            .largest_total_power_square(SQUARE_SIZE)
+            .expect("❌")
+            .0
+    }
+
+    fn part02(&self) -> (Cell, usize) {
+        self.grid
+            .largest_total_power_square_of_any_size()
+            .expect("❌")
+    }
+
+    /// Power level of every fuel cell, a row of the grid per line
+    pub fn heatmap(&self, options: HeatmapOptions) -> String {
+        let grid = &self.grid;
+        let best_totals: Vec<_> = if options.best_totals {
+            (1..=grid.width.min(grid.height))
+                .filter_map(|size| Some((size, grid.largest_total_power_square(size)?)))
+                .collect()
+        } else {
+            vec![]
+        };
+        let mut heatmap = String::new();
+        if options.format == HeatmapFormat::Pgm {
+            heatmap.push_str("P2\n");
+            for (size, ((x, y), power)) in &best_totals {
+                heatmap.push_str(&format!("# size {}: {},{} total {}\n", size, x, y, power));
+            }
+            heatmap.push_str(&format!("{} {}\n9\n", grid.width, grid.height));
+        }
+        let separator = match options.format {
+            HeatmapFormat::Pgm => " ",
+            HeatmapFormat::Csv => ",",
         };
-        day.fill_prefixed_power_levels();
-        day
+        for y in 1..=grid.height {
+            let row: Vec<_> = (1..=grid.width)
+                .map(|x| match options.format {
+                    HeatmapFormat::Pgm => grid.power_level((x, y)) + 5,
+                    HeatmapFormat::Csv => grid.power_level((x, y)),
+                })
+                .map(|level| level.to_string())
+                .collect();
+            heatmap.push_str(&row.join(separator));
+            heatmap.push('\n');
+        }
+        if options.format == HeatmapFormat::Csv && options.best_totals {
+            heatmap.push_str("\nsize,x,y,total\n");
+            for (size, ((x, y), power)) in &best_totals {
+                heatmap.push_str(&format!("{},{},{},{}\n", size, x, y, power));
+            }
+        }
+        heatmap
+    }
+}
+
+impl Grid {
+    fn new(width: usize, height: usize, serial_number: isize) -> Self {
+        let mut grid = Grid {
+            width,
+            height,
+            serial_number,
+            prefixed_power_levels: vec![vec![0; height + 2]; width + 2],
+        };
+        grid.fill_prefixed_power_levels();
+        grid
     }
 
     fn fill_prefixed_power_levels(&mut self) {
-        for x in (1..=GRID_SIZE).rev() {
-            for y in (1..=GRID_SIZE).rev() {
+        for x in (1..=self.width).rev() {
+            for y in (1..=self.height).rev() {
                 self.prefixed_power_levels[x][y] = self.power_level((x, y))
                     + self.prefixed_power_levels[x][y + 1]
                     + self.prefixed_power_levels[x + 1][y]
@@ -45,48 +139,90 @@ impl Day {
 
     fn power_level(&self, (x, y): Cell) -> isize {
         let rack_id = x as isize + 10;
-        let power_level = rack_id * y as isize + self.grid_serial_number;
+        let power_level = rack_id * y as isize + self.serial_number;
         power_level * rack_id / 100 % 10 - 5
     }
 
-    fn part01(&self) -> Cell {
-        self.largest_total_power_square(SQUARE_SIZE).0
+    fn total_power(&self, (x, y): Cell, size: usize) -> isize {
+        self.prefixed_power_levels[x][y]
+            - self.prefixed_power_levels[x][y + size]
+            - self.prefixed_power_levels[x + size][y]
+            + self.prefixed_power_levels[x + size][y + size]
     }
 
-    fn part02(&self) -> (Cell, usize) {
-        let mut max_cell = (0, 0);
-        let mut max_power_level = isize::MIN;
-        let mut max_size = 0;
-        for size in 1..=GRID_SIZE {
-            let (cell, power_level) = self.largest_total_power_square(size);
-            if power_level > max_power_level {
-                max_cell = cell;
-                max_power_level = power_level;
-                max_size = size;
-            }
+    /// Top-left cell and total power of the square of `size` with the largest total power,
+    /// among all the ones fitting in the grid
+    fn largest_total_power_square(&self, size: usize) -> Option<(Cell, isize)> {
+        if size == 0 || size > self.width || size > self.height {
+            return None;
         }
-        (max_cell, max_size)
-    }
-
-    fn largest_total_power_square(&self, square_size: usize) -> (Cell, isize) {
         let mut max_cell = (0, 0);
         let mut max_power_level = isize::MIN;
-        for x in square_size..=GRID_SIZE - square_size {
-            for y in square_size..=GRID_SIZE - square_size {
-                let power_level = self.prefixed_power_levels[x][y]
-                    - self.prefixed_power_levels[x][y + square_size]
-                    - self.prefixed_power_levels[x + square_size][y]
-                    + self.prefixed_power_levels[x + square_size][y + square_size];
+        for x in 1..=self.width - size + 1 {
+            for y in 1..=self.height - size + 1 {
+                let power_level = self.total_power((x, y), size);
                 if power_level > max_power_level {
                     max_power_level = power_level;
                     max_cell = (x, y);
                 }
             }
         }
-        (max_cell, max_power_level)
+        Some((max_cell, max_power_level))
+    }
+
+    /// Searches batches of sizes in parallel, skipping the sizes whose squares cannot beat the
+    /// best total found so far according to `upper_bound`
+    fn largest_total_power_square_of_any_size(&self) -> Option<(Cell, usize)> {
+        let max_size = self.width.min(self.height);
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        let mut upper_bounds = vec![0; max_size + 1];
+        let mut best: Option<(isize, Cell, usize)> = None;
+        for first in (1..=max_size).step_by(threads) {
+            let mut sizes = vec![];
+            for size in first..(first + threads).min(max_size + 1) {
+                match upper_bound(&upper_bounds[..first], size) {
+                    Some(bound) if best.is_some_and(|(power, _, _)| bound <= power) => {
+                        upper_bounds[size] = bound;
+                    }
+                    _ => sizes.push(size),
+                }
+            }
+            let squares: Vec<_> = thread::scope(|scope| {
+                let handles: Vec<_> = sizes
+                    .iter()
+                    .map(|&size| scope.spawn(move || self.largest_total_power_square(size)))
+                    .collect();
+                handles.into_iter().map(|h| h.join().expect("❌")).collect()
+            });
+            for (size, square) in sizes.into_iter().zip(squares) {
+                let (cell, power) = square.expect("❌");
+                upper_bounds[size] = power;
+                if best.is_none_or(|(max_power, _, _)| power > max_power) {
+                    best = Some((power, cell, size));
+                }
+            }
+        }
+        best.map(|(_, cell, size)| (cell, size))
     }
 }
 
+/// Bound on the total power of any square of `size`, from the bounds of the smaller sizes: a
+/// square of even size splits into four squares of half its size, and one of odd size 2m + 1
+/// into a square of size m + 1, three of size m and 2m single cells
+fn upper_bound(bounds: &[isize], size: usize) -> Option<isize> {
+    let half = size / 2;
+    if half == 0 || half + size % 2 >= bounds.len() {
+        return None;
+    }
+    Some(if size.is_multiple_of(2) {
+        bounds[half].saturating_mul(4)
+    } else {
+        bounds[half + 1]
+            .saturating_add(bounds[half].saturating_mul(3))
+            .saturating_add(bounds[1].saturating_mul(2 * half as isize))
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -110,14 +246,105 @@ mod tests {
         test_part02_02: (part02, vec!["42"], "232,251,12"),
     }
 
+    #[test]
+    fn test_part02_matches_exhaustive_search() {
+        for serial in [18, 42, 1, 6392, 7989] {
+            let grid = Day::read_from(&[serial.to_string()]).grid;
+            let exhaustive = (1..=GRID_SIZE)
+                .filter_map(|size| Some((grid.largest_total_power_square(size)?, size)))
+                .fold(((0, 0), isize::MIN, 0), |best, ((cell, power), size)| {
+                    if power > best.1 {
+                        (cell, power, size)
+                    } else {
+                        best
+                    }
+                });
+            assert_eq!(
+                grid.largest_total_power_square_of_any_size(),
+                Some((exhaustive.0, exhaustive.2)),
+                "serial {}",
+                serial
+            );
+        }
+    }
+
+    #[test]
+    fn test_largest_total_power_square_covers_every_corner() {
+        for (width, height, serial) in [(7, 5, 18), (5, 9, 42), (12, 12, 7989)] {
+            let grid = Grid::new(width, height, serial);
+            for size in 1..=width.min(height) {
+                let mut best = None;
+                for x in 1..=width - size + 1 {
+                    for y in 1..=height - size + 1 {
+                        let power: isize = (x..x + size)
+                            .flat_map(|cx| (y..y + size).map(move |cy| (cx, cy)))
+                            .map(|cell| grid.power_level(cell))
+                            .sum();
+                        if best.is_none_or(|(_, max_power)| power > max_power) {
+                            best = Some(((x, y), power));
+                        }
+                    }
+                }
+                assert_eq!(grid.largest_total_power_square(size), best);
+            }
+            assert_eq!(grid.largest_total_power_square(width.min(height) + 1), None);
+        }
+    }
+
+    #[test]
+    fn test_largest_total_power_square_at_edges() {
+        let grid = Grid::new(3, 3, 18);
+        assert_eq!(
+            grid.largest_total_power_square(3),
+            Some(((1, 1), grid.total_power((1, 1), 3)))
+        );
+        // The best 3x3 square for serial 16 starts on the second row
+        let grid = Grid::new(GRID_SIZE, GRID_SIZE, 16);
+        assert_eq!(grid.largest_total_power_square(3), Some(((237, 2), 31)));
+    }
+
+    #[test]
+    fn test_heatmap() {
+        let day = Day {
+            grid: Grid::new(3, 2, 8),
+        };
+        assert_eq!(
+            day.heatmap(HeatmapOptions::default()),
+            "P2\n3 2\n9\n2 2 2\n3 3 4\n"
+        );
+        assert_eq!(
+            day.heatmap(HeatmapOptions {
+                format: HeatmapFormat::Csv,
+                best_totals: true,
+            }),
+            "-3,-3,-3\n-2,-2,-1\n\nsize,x,y,total\n1,3,2,-1\n2,2,1,-9\n"
+        );
+        let pgm = day.heatmap(HeatmapOptions {
+            best_totals: true,
+            ..HeatmapOptions::default()
+        });
+        assert!(pgm.starts_with("P2\n# size 1: 3,2 total -1\n# size 2: 2,1 total -9\n3 2\n"));
+    }
+
+    #[test]
+    fn test_upper_bound() {
+        let mut bounds = vec![0, 4, 10, 7];
+        assert_eq!(upper_bound(&bounds, 1), None);
+        assert_eq!(upper_bound(&bounds, 4), Some(40));
+        assert_eq!(upper_bound(&bounds, 5), Some(7 + 3 * 10 + 4 * 4));
+        assert_eq!(upper_bound(&bounds, 7), None);
+        bounds[2] = isize::MIN;
+        assert_eq!(upper_bound(&bounds, 4), Some(isize::MIN));
+    }
+
     macro_rules! test_power_level {
         ($($name:ident: $values:expr,)*) => {
             $(
                 #[test]
                 fn $name() {
-                    let (cell, grid_serial_number, expected) = $values;
-                    let day = Day { grid_serial_number, ..Default::default() };
-                    assert_eq!(day.power_level(cell), expected);
+                    let (cell, serial_number, expected) = $values;
+                    let grid = Grid { serial_number, ..Default::default() };
+                    assert_eq!(grid.power_level(cell), expected);
                 }
             )*
         }
@@ -135,17 +362,9 @@ mod tests {
             $(
                 #[test]
                 fn $name() {
-                    let ((x, y), grid_serial_number, expected) = $values;
-                    let mut day = Day {
-                        grid_serial_number,
-                        prefixed_power_levels: vec![vec![0; GRID_SIZE + 2]; GRID_SIZE + 2],
-                    };
-                    day.fill_prefixed_power_levels();
-                    let total_power = day.prefixed_power_levels[x][y]
-                        - day.prefixed_power_levels[x][y + SQUARE_SIZE]
-                        - day.prefixed_power_levels[x + SQUARE_SIZE][y]
-                        + day.prefixed_power_levels[x + SQUARE_SIZE][y + SQUARE_SIZE];
-                    assert_eq!(total_power, expected);
+                    let (cell, serial_number, expected) = $values;
+                    let grid = Grid::new(GRID_SIZE, GRID_SIZE, serial_number);
+                    assert_eq!(grid.total_power(cell, SQUARE_SIZE), expected);
                 }
             )*
         }
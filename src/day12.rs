@@ -1,12 +1,82 @@
 /// Day 12 (https://adventofcode.com/2018/day/12)
 extern crate text_io;
 
+use crate::input;
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+const GENERATIONS: usize = 50_000_000_000;
+const STABILISATION_LIMIT: usize = 1_000;
+const PATTERNS: usize = 32;
+const PATTERN_LENGTH: usize = 5;
+const EMPTY: usize = 0b00000;
+// Patterns around the leftmost or rightmost plant deciding whether plants appear beyond it
+const PLANT_THEN_EMPTY: usize = 0b10000;
+const EMPTY_PLANT_THEN_EMPTY: usize = 0b01000;
+const TWO_PLANTS_THEN_EMPTY: usize = 0b11000;
+const EMPTY_THEN_PLANT: usize = 0b00001;
+const EMPTY_THEN_PLANT_EMPTY: usize = 0b00010;
+const EMPTY_THEN_TWO_PLANTS: usize = 0b00011;
+
 pub fn part01<T: AsRef<str>>(lines: &[T]) -> isize {
-    Day::read_from(lines).part01()
+    Day::read_from(lines).expect("❌").part01()
 }
 
 pub fn part02<T: AsRef<str>>(lines: &[T]) -> isize {
-    Day::read_from(lines).part02()
+    Day::read_from(lines).expect("❌").part02()
+}
+
+/// Sum of the numbers of the pots with a plant after any number of `generations`
+pub fn pot_sum_after<T: AsRef<str>>(lines: &[T], generations: usize) -> Result<isize, String> {
+    Day::read_from(lines)?.sum_after(generations, STABILISATION_LIMIT)
+}
+
+/// Patterns the notes say nothing about, which then grow no plant
+pub fn missing_patterns<T: AsRef<str>>(lines: &[T]) -> Result<Vec<String>, String> {
+    let day = Day::read_from(lines)?;
+    Ok((0..PATTERNS)
+        .filter(|&index| day.noted & (1 << index) == 0)
+        .map(pattern_string)
+        .collect())
+}
+
+/// How the plants evolve under the notes, told from the rules alone when possible
+pub fn behaviour<T: AsRef<str>>(lines: &[T]) -> Result<Behaviour, String> {
+    Ok(Day::read_from(lines)?.behaviour(STABILISATION_LIMIT))
+}
+
+/// Sum of the numbers of the pots with a plant after stepping through every one of the
+/// `generations` on bit-packed pots
+pub fn simulated_pot_sum_after<T: AsRef<str>>(lines: &[T], generations: usize) -> isize {
+    let day = Day::read_from(lines).expect("❌");
+    let mut pots = PackedPots::new(&day.state, -day.prepend_count);
+    let rules = day.rules();
+    for _ in 0..generations {
+        pots.step(rules);
+    }
+    pots.sum_indexes()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Behaviour {
+    /// No plant is left from `generation` on
+    DiesOut { generation: usize },
+    /// The leftmost and the rightmost plants get further apart every generation, whatever the
+    /// other plants
+    GrowsUnbounded,
+    /// From `generation` on, the plants repeat every `period` generations, `shift` pots further
+    /// each time, which makes them a glider when `shift` is not zero
+    Cycles {
+        generation: usize,
+        period: usize,
+        shift: isize,
+    },
+    /// No plant ever appears beyond the leftmost or rightmost ones, so the plants eventually
+    /// cycle, though not within the stabilisation limit
+    Bounded,
+    /// Nothing could be told within the stabilisation limit
+    Unknown,
 }
 
 #[derive(Debug, Default)]
@@ -14,43 +84,53 @@ struct Day {
     state: Vec<isize>,
     notes: Vec<isize>,
     prepend_count: isize,
+    /// Bit set of the patterns having a note
+    noted: u32,
+}
+
+/// Pots packed 64 to a word, the lowest bit of the first word being pot number `first`
+#[derive(Debug, Default, PartialEq)]
+struct PackedPots {
+    words: Vec<u64>,
+    first: isize,
 }
 
 impl Day {
-    fn read_from<T: AsRef<str>>(lines: &[T]) -> Self {
-        let mut lines_iter = lines.iter();
+    fn read_from<T: AsRef<str>>(lines: &[T]) -> Result<Self, String> {
+        let initial_state = lines.first().ok_or("missing initial state")?.as_ref();
         let mut day = Day {
-            state: lines_iter
-                .next()
-                .expect("❌")
-                .as_ref()
-                .split(' ')
-                .nth(2)
-                .expect("❌")
-                .chars()
-                .map(|c| (c == '#') as isize)
-                .collect(),
-            notes: vec![0; 32],
-            prepend_count: 0,
+            state: initial_state
+                .strip_prefix("initial state: ")
+                .and_then(parse_pots)
+                .ok_or_else(|| {
+                    input::at_line(0, format!("malformed initial state `{}`", initial_state))
+                })?,
+            notes: vec![0; PATTERNS],
+            ..Day::default()
         };
-        lines_iter.next();
-        for line in lines_iter {
-            let mut line_iter = line.as_ref().split(" => ");
-            let note_index = line_iter
-                .next()
-                .expect("❌")
-                .replace('#', "1")
-                .replace('.', "0");
-            let note_value = line_iter
-                .next()
-                .expect("❌")
-                .replace('#', "1")
-                .replace('.', "0");
-            let index = isize::from_str_radix(&note_index, 2).expect("❌");
-            let value = isize::from_str_radix(&note_value, 2).expect("❌");
-            day.notes[index as usize] = value;
-        }
-        day
+        for (index, line) in lines.iter().enumerate().skip(1) {
+            let line = line.as_ref();
+            if line.is_empty() {
+                continue;
+            }
+            let (pattern, value) = parse_note(line)
+                .ok_or_else(|| input::at_line(index, format!("malformed note `{}`", line)))?;
+            if day.noted & (1 << pattern) != 0 && day.notes[pattern] != value {
+                return Err(input::at_line(
+                    index,
+                    format!("conflicting note for `{}`", &line[..PATTERN_LENGTH]),
+                ));
+            }
+            if pattern == EMPTY && value == 1 {
+                return Err(input::at_line(
+                    index,
+                    "`..... => #` would grow plants in the infinitely many empty pots",
+                ));
+            }
+            day.notes[pattern] = value;
+            day.noted |= 1 << pattern;
+        }
+        Ok(day)
     }
 
     fn part01(&mut self) -> isize {
@@ -61,10 +141,103 @@ impl Day {
     }
 
     fn part02(&mut self) -> isize {
-        for _ in 0..500 {
-            self.step();
+        self.sum_after(GENERATIONS, STABILISATION_LIMIT)
+            .expect("❌")
+    }
+
+    /// Extrapolates the sum from the first repetition of the plants, since the pattern then
+    /// keeps cycling and shifting by the same amount
+    fn sum_after(&mut self, generations: usize, limit: usize) -> Result<isize, String> {
+        let mut history = vec![];
+        match self.find_repeat(generations.min(limit), &mut history) {
+            Some((start, generation, shift)) => {
+                let period = generation - start;
+                let remaining = generations - generation;
+                let (sum, plants) = history[start + remaining % period];
+                let periods = (remaining / period + 1) as isize;
+                Ok(sum + periods * shift * plants)
+            }
+            None if generations <= limit => Ok(history[generations].0),
+            None => Err(format!(
+                "plants did not stabilise within {} generations",
+                limit
+            )),
+        }
+    }
+
+    /// Steps until the plants form a pattern already seen in some earlier generation, maybe
+    /// shifted by some pots, returning that earlier generation, the current one and the shift,
+    /// after recording the sum and the number of plants of each generation in `history`
+    fn find_repeat(
+        &mut self,
+        generations: usize,
+        history: &mut Vec<(isize, isize)>,
+    ) -> Option<(usize, usize, isize)> {
+        let mut seen = HashMap::new();
+        for generation in 0..=generations {
+            let (pattern, offset) = self.pattern();
+            history.push((self.sum_indexes(), pattern.iter().sum()));
+            match seen.entry(pattern) {
+                Entry::Occupied(entry) => {
+                    let (start, start_offset) = *entry.get();
+                    return Some((start, generation, offset - start_offset));
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert((generation, offset));
+                }
+            }
+            if generation < generations {
+                self.step();
+            }
+        }
+        None
+    }
+
+    fn behaviour(&mut self, limit: usize) -> Behaviour {
+        let grows = |pattern: usize| self.notes[pattern] == 1;
+        let right_always_grows = grows(PLANT_THEN_EMPTY)
+            || grows(EMPTY_PLANT_THEN_EMPTY) && grows(TWO_PLANTS_THEN_EMPTY);
+        let left_always_grows = grows(EMPTY_THEN_PLANT)
+            || grows(EMPTY_THEN_PLANT_EMPTY) && grows(EMPTY_THEN_TWO_PLANTS);
+        let bounded = [
+            PLANT_THEN_EMPTY,
+            EMPTY_PLANT_THEN_EMPTY,
+            TWO_PLANTS_THEN_EMPTY,
+            EMPTY_THEN_PLANT,
+            EMPTY_THEN_PLANT_EMPTY,
+            EMPTY_THEN_TWO_PLANTS,
+        ]
+        .iter()
+        .all(|&pattern| !grows(pattern));
+        if right_always_grows && left_always_grows && self.state.contains(&1) {
+            return Behaviour::GrowsUnbounded;
+        }
+        match self.find_repeat(limit, &mut vec![]) {
+            Some((generation, _, _)) if !self.state.contains(&1) => {
+                Behaviour::DiesOut { generation }
+            }
+            Some((start, generation, shift)) => Behaviour::Cycles {
+                generation: start,
+                period: generation - start,
+                shift,
+            },
+            None if bounded => Behaviour::Bounded,
+            None => Behaviour::Unknown,
+        }
+    }
+
+    /// Pots from the leftmost to the rightmost plant, and the number of the leftmost one
+    fn pattern(&self) -> (Vec<isize>, isize) {
+        match self.state.iter().position(|&pot| pot == 1) {
+            Some(first) => {
+                let last = self.state.iter().rposition(|&pot| pot == 1).expect("❌");
+                (
+                    self.state[first..=last].to_vec(),
+                    first as isize - self.prepend_count,
+                )
+            }
+            None => (vec![], 0),
         }
-        self.sum_indexes() * 100_000_000
     }
 
     fn step(&mut self) {
@@ -87,6 +260,25 @@ impl Day {
         self.state = new_state;
     }
 
+    /// Rules as a bit set, bit `n` telling whether pattern `n` grows a plant
+    fn rules(&self) -> u32 {
+        self.notes
+            .iter()
+            .enumerate()
+            .fold(0, |rules, (index, &value)| {
+                rules | ((value as u32) << index)
+            })
+    }
+
+    fn plants(&self) -> Vec<isize> {
+        self.state
+            .iter()
+            .enumerate()
+            .filter(|&(_, &pot)| pot == 1)
+            .map(|(i, _)| i as isize - self.prepend_count)
+            .collect()
+    }
+
     fn sum_indexes(&self) -> isize {
         self.state
             .iter()
@@ -96,10 +288,131 @@ impl Day {
     }
 }
 
+impl PackedPots {
+    fn new(state: &[isize], first: isize) -> Self {
+        let mut words = vec![0; state.len() / 64 + 1];
+        for (i, _) in state.iter().enumerate().filter(|&(_, &pot)| pot == 1) {
+            words[i / 64] |= 1 << (i % 64);
+        }
+        PackedPots { words, first }
+    }
+
+    /// Computes the next generation in place, following `rules` whose bit `n` tells whether a
+    /// pot gets a plant when it and its four neighbours read `n` in binary, leftmost pot first
+    fn step(&mut self, rules: u32) {
+        self.make_room();
+        // Old values of the two pots on the left of the pot being updated, of that pot and of the
+        // one on its right, all empty for the first pot thanks to `make_room`
+        let mut window = 0;
+        for i in 0..self.words.len() {
+            let current = self.words[i];
+            let next = self.words.get(i + 1).copied().unwrap_or(0);
+            let mut word = 0;
+            for bit in 0..64 {
+                let ahead = if bit < 62 {
+                    current >> (bit + 2)
+                } else {
+                    next >> (bit - 62)
+                };
+                window = ((window << 1) | (ahead & 1) as u32) & 31;
+                word |= u64::from((rules >> window) & 1) << bit;
+            }
+            self.words[i] = word;
+        }
+        self.trim();
+    }
+
+    /// Pads with empty words so plants can spread two pots beyond the current ones
+    fn make_room(&mut self) {
+        if self.words[0] & 3 != 0 {
+            self.words.insert(0, 0);
+            self.first -= 64;
+        }
+        if self.words[self.words.len() - 1] >> 62 != 0 {
+            self.words.push(0);
+        }
+    }
+
+    fn trim(&mut self) {
+        let empty = self.words.iter().take_while(|&&w| w == 0).count();
+        let empty = empty.min(self.words.len() - 1);
+        if empty > 0 {
+            self.words.drain(..empty);
+            self.first += 64 * empty as isize;
+        }
+        while self.words.len() > 1 && self.words[self.words.len() - 1] == 0 {
+            self.words.pop();
+        }
+    }
+
+    fn plants(&self) -> Vec<isize> {
+        let mut plants = vec![];
+        for (i, &word) in self.words.iter().enumerate() {
+            let mut word = word;
+            while word != 0 {
+                plants.push(self.first + 64 * i as isize + word.trailing_zeros() as isize);
+                word &= word - 1;
+            }
+        }
+        plants
+    }
+
+    fn sum_indexes(&self) -> isize {
+        self.plants().iter().sum()
+    }
+}
+
+fn parse_pots(pots: &str) -> Option<Vec<isize>> {
+    pots.chars()
+        .map(|c| match c {
+            '#' => Some(1),
+            '.' => Some(0),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Index of the pattern, reading plants as ones from the leftmost pot, and the resulting pot
+fn parse_note(line: &str) -> Option<(usize, isize)> {
+    let (pattern, value) = line.split_once(" => ")?;
+    let pattern = parse_pots(pattern).filter(|p| p.len() == PATTERN_LENGTH)?;
+    let value = parse_pots(value).filter(|v| v.len() == 1)?[0];
+    let index = pattern
+        .iter()
+        .fold(0, |index, &pot| (index << 1) | pot as usize);
+    Some((index, value))
+}
+
+fn pattern_string(index: usize) -> String {
+    (0..PATTERN_LENGTH)
+        .rev()
+        .map(|bit| if index >> bit & 1 == 1 { '#' } else { '.' })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const EXAMPLE: [&str; 16] = [
+        "initial state: #..#.#..##......###...###",
+        "",
+        "...## => #",
+        "..#.. => #",
+        ".#... => #",
+        ".#.#. => #",
+        ".#.## => #",
+        ".##.. => #",
+        ".#### => #",
+        "#.#.# => #",
+        "#.### => #",
+        "##.#. => #",
+        "##.## => #",
+        "###.. => #",
+        "###.# => #",
+        "####. => #",
+    ];
+
     macro_rules! test_parts {
         ($($name:ident: $values:expr,)*) => {
             $(
@@ -113,41 +426,181 @@ mod tests {
     }
 
     test_parts! {
-        test_part01_01: (part01, vec![
-            "initial state: #..#.#..##......###...###",
+        test_part01_01: (part01, EXAMPLE.to_vec(), 325),
+        test_part02_01: (part02, EXAMPLE.to_vec(), 999_999_999_374),
+    }
+
+    #[test]
+    fn test_packed_pots_match_day() {
+        let inputs = [
+            EXAMPLE.iter().map(|l| l.to_string()).collect(),
+            crate::input::read_lines_from_input("data/day12"),
+        ];
+        for lines in inputs {
+            let mut day = Day::read_from(&lines).unwrap();
+            let mut pots = PackedPots::new(&day.state, 0);
+            let rules = day.rules();
+            for generation in 0..300 {
+                assert_eq!(pots.plants(), day.plants(), "generation {}", generation);
+                day.step();
+                pots.step(rules);
+            }
+        }
+    }
+
+    #[test]
+    fn test_packed_pots_grow_both_ways() {
+        let mut pots = PackedPots::new(&[1], 0);
+        let rules = !1;
+        for _ in 0..100 {
+            pots.step(rules);
+        }
+        assert_eq!(pots.plants(), (-200..=200).collect::<Vec<_>>());
+        assert_eq!(pots.words.len(), 8);
+    }
+
+    #[test]
+    fn test_simulated_pot_sum_after() {
+        assert_eq!(simulated_pot_sum_after(&EXAMPLE, 20), 325);
+        assert_eq!(
+            simulated_pot_sum_after(&EXAMPLE, 100_000),
+            pot_sum_after(&EXAMPLE, 100_000).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_pot_sum_after_cycle() {
+        // A lone plant grows into a pair which collapses into a lone plant two pots further
+        let lines = [
+            "initial state: #",
             "",
-            "...## => #",
             "..#.. => #",
             ".#... => #",
-            ".#.#. => #",
-            ".#.## => #",
-            ".##.. => #",
-            ".#### => #",
-            "#.#.# => #",
-            "#.### => #",
-            "##.#. => #",
-            "##.## => #",
-            "###.. => #",
-            "###.# => #",
-            "####. => #",
-        ], 325),
-        test_part02_01: (part02, vec![
-            "initial state: #..#.#..##......###...###",
+            "##... => #",
+        ];
+        assert_eq!(pot_sum_after(&lines, 0), Ok(0));
+        assert_eq!(pot_sum_after(&lines, 3), Ok(2 + 3));
+        assert_eq!(pot_sum_after(&lines, 1000), Ok(1000));
+        assert_eq!(pot_sum_after(&lines, 1001), Ok(1000 + 1001));
+    }
+
+    #[test]
+    fn test_sum_after_without_stabilisation() {
+        // Every plant spreads to its neighbours, so the row of plants keeps growing
+        let mut lines = vec!["initial state: #".to_string(), String::new()];
+        for index in 1..32 {
+            let pattern: String = (0..5)
+                .map(|bit| {
+                    if index >> (4 - bit) & 1 == 1 {
+                        '#'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            lines.push(format!("{} => #", pattern));
+        }
+        let mut day = Day::read_from(&lines).unwrap();
+        assert_eq!(
+            day.sum_after(GENERATIONS, 100),
+            Err("plants did not stabilise within 100 generations".to_string()),
+        );
+        assert_eq!(pot_sum_after(&lines, 3), Ok(0));
+    }
+
+    macro_rules! test_read_from_errors {
+        ($($name:ident: $values:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (lines, expected): (Vec<&str>, &str) = $values;
+                    assert_eq!(Day::read_from(&lines).map(|_| ()), Err(expected.to_string()));
+                }
+            )*
+        }
+    }
+
+    test_read_from_errors! {
+        test_read_from_errors_01: (vec![], "missing initial state"),
+        test_read_from_errors_02: (vec!["initial: #.#"], "line 1: malformed initial state `initial: #.#`"),
+        test_read_from_errors_03: (vec!["initial state: #o#"], "line 1: malformed initial state `initial state: #o#`"),
+        test_read_from_errors_04: (vec!["initial state: #", "", "..#. => #"], "line 3: malformed note `..#. => #`"),
+        test_read_from_errors_05: (vec!["initial state: #", "", "..#.. -> #"], "line 3: malformed note `..#.. -> #`"),
+        test_read_from_errors_06: (vec!["initial state: #", "", "..#.. => ##"], "line 3: malformed note `..#.. => ##`"),
+        test_read_from_errors_07: (
+            vec!["initial state: #", "", "..#.. => #", "..#.. => ."],
+            "line 4: conflicting note for `..#..`",
+        ),
+        test_read_from_errors_08: (
+            vec!["initial state: #", "", "..... => #"],
+            "line 3: `..... => #` would grow plants in the infinitely many empty pots",
+        ),
+    }
+
+    #[test]
+    fn test_missing_patterns() {
+        let lines = [
+            "initial state: #",
             "",
-            "...## => #",
             "..#.. => #",
-            ".#... => #",
-            ".#.#. => #",
-            ".#.## => #",
-            ".##.. => #",
-            ".#### => #",
-            "#.#.# => #",
-            "#.### => #",
-            "##.#. => #",
-            "##.## => #",
-            "###.. => #",
-            "###.# => #",
-            "####. => #",
-        ], 937_400_000_000),
+            "..#.. => #",
+            "..... => .",
+        ];
+        let missing = missing_patterns(&lines).unwrap();
+        assert_eq!(missing.len(), 30);
+        assert_eq!(missing[..3], ["....#", "...#.", "...##"]);
+        assert!(!missing.contains(&"..#..".to_string()));
+        let lines = crate::input::read_lines_from_input("data/day12");
+        assert_eq!(missing_patterns(&lines), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_behaviour() {
+        assert_eq!(
+            behaviour(&EXAMPLE),
+            Ok(Behaviour::Cycles {
+                generation: 86,
+                period: 1,
+                shift: 1,
+            })
+        );
+        assert_eq!(
+            behaviour(&[
+                "initial state: #",
+                "",
+                "..#.. => #",
+                ".#... => #",
+                "##... => #"
+            ]),
+            Ok(Behaviour::Cycles {
+                generation: 0,
+                period: 2,
+                shift: 2,
+            })
+        );
+        assert_eq!(
+            behaviour(&["initial state: #..#", "", "#.... => #", "....# => #"]),
+            Ok(Behaviour::GrowsUnbounded)
+        );
+        assert_eq!(
+            behaviour(&["initial state: #..#", "", "#.... => #"]),
+            Ok(Behaviour::Cycles {
+                generation: 1,
+                period: 1,
+                shift: 2,
+            })
+        );
+        assert_eq!(
+            behaviour(&["initial state: ..", "", "#.... => #"]),
+            Ok(Behaviour::DiesOut { generation: 0 })
+        );
+        assert_eq!(
+            behaviour(&["initial state: ##.#", "", "..#.. => ."]),
+            Ok(Behaviour::DiesOut { generation: 1 })
+        );
+        let mut day = Day::read_from(&["initial state: #", "", "..#.. => #"]).unwrap();
+        assert_eq!(day.behaviour(0), Behaviour::Bounded);
+        let mut day = Day::read_from(&EXAMPLE).unwrap();
+        assert_eq!(day.behaviour(0), Behaviour::Unknown);
     }
 }
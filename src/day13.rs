@@ -1,32 +1,73 @@
 /// Day 13 (https://adventofcode.com/2018/day/13)
 extern crate text_io;
 
-use std::cmp::Ordering;
-use std::collections::BinaryHeap;
 use std::collections::HashMap;
 use std::collections::HashSet;
 
 pub fn part01<T: AsRef<str>>(lines: &[T]) -> String {
-    let (x, y) = Day::read_from(lines).part01();
+    let (x, y) = Day::read_from(lines).expect("❌").part01();
     format!("{},{}", x, y)
 }
 
 pub fn part02<T: AsRef<str>>(lines: &[T]) -> String {
-    let (x, y) = Day::read_from(lines).part02();
+    let (x, y) = Day::read_from(lines).expect("❌").part02();
     format!("{},{}", x, y)
 }
 
+pub fn simulation<T: AsRef<str>>(lines: &[T]) -> Result<Simulation, String> {
+    Ok(Day::read_from(lines)?.simulation)
+}
+
 #[derive(Debug, Default)]
 struct Day {
+    simulation: Simulation,
+}
+
+/// Carts moving along the tracks one tick at a time, keeping a log of their collisions
+#[derive(Clone, Debug, Default)]
+pub struct Simulation {
     tracks: HashMap<Location, char>,
-    carts: BinaryHeap<Cart>,
-    occupied: HashSet<Location>,
+    start: Vec<Cart>,
+    carts: Vec<Cart>,
+    ticks: usize,
+    collisions: Vec<Collision>,
 }
 
 type Location = (usize, usize);
+type Direction = (isize, isize);
+
+const NORTH: Direction = (0, -1);
+const EAST: Direction = (1, 0);
+const SOUTH: Direction = (0, 1);
+const WEST: Direction = (-1, 0);
+const ALL_DIRECTIONS: [Direction; 4] = [NORTH, EAST, SOUTH, WEST];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Collision {
+    pub tick: usize,
+    pub location: Location,
+    /// Cart that moved into the other one, then the one it hit
+    pub carts: [usize; 2],
+}
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Event {
+    Moved {
+        cart: usize,
+        from: Location,
+        to: Location,
+    },
+    Crashed(Collision),
+    Removed {
+        cart: usize,
+        location: Location,
+    },
+}
+
+/// Carts are numbered in reading order of their initial location
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
 struct Cart {
+    id: usize,
     x: usize,
     y: usize,
     vx: isize,
@@ -34,32 +75,51 @@ struct Cart {
     intersection_count: usize,
 }
 
-impl Ord for Cart {
-    fn cmp(&self, other: &Self) -> Ordering {
-        other.y.cmp(&self.y).then_with(|| other.x.cmp(&self.x))
-    }
-}
-
-impl PartialOrd for Cart {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
 impl Day {
-    fn read_from<T: AsRef<str>>(lines: &[T]) -> Self {
-        let mut day = Day::default();
+    fn read_from<T: AsRef<str>>(lines: &[T]) -> Result<Self, String> {
+        let mut glyphs = HashMap::new();
         for (y, line) in lines.iter().enumerate() {
             for (x, c) in line.as_ref().chars().enumerate() {
-                if c != ' ' {
-                    day.add_element(x, y, c);
+                match c {
+                    ' ' => {}
+                    '-' | '|' | '/' | '\\' | '+' | '^' | 'v' | '<' | '>' => {
+                        glyphs.insert((x, y), c);
+                    }
+                    _ => return Err(format!("unknown glyph `{}` at {},{}", c, x, y)),
+                }
+            }
+        }
+        let mut locations: Vec<_> = glyphs.keys().copied().collect();
+        locations.sort_by_key(|&(x, y)| (y, x));
+        let mut options = glyphs.iter().map(|(&l, &c)| (l, connections(c))).collect();
+        narrow(&mut options);
+        let mut day = Day::default();
+        for location in locations {
+            let c = glyphs[&location];
+            let joined = |directions: &&Vec<Direction>| {
+                directions
+                    .iter()
+                    .all(|&d| link(&options, location, d) != Some(false))
+            };
+            let (x, y) = location;
+            match options[&location].iter().find(joined) {
+                Some(directions) => day.add_element(x, y, c, directions.len() == 4),
+                None if "^v<>".contains(c) => {
+                    return Err(format!(
+                        "cart at {},{} is not on a straight track or an intersection",
+                        x, y
+                    ))
                 }
+                None => return Err(format!("track `{}` at {},{} has a dangling end", c, x, y)),
             }
         }
-        day
+        day.simulation.carts = day.simulation.start.clone();
+        Ok(day)
     }
 
-    fn add_element(&mut self, x: usize, y: usize, c: char) {
+    /// Adds a track section, or a cart and the section under it, which is an intersection when
+    /// `intersection` is set
+    fn add_element(&mut self, x: usize, y: usize, c: char, intersection: bool) {
         let (vx, vy, new_c) = match c {
             '^' => (0, -1, '|'),
             'v' => (0, 1, '|'),
@@ -67,70 +127,160 @@ impl Day {
             '>' => (1, 0, '-'),
             _ => (0, 0, c),
         };
-        self.tracks.insert((x, y), new_c);
+        let simulation = &mut self.simulation;
         if new_c != c {
-            self.carts.push(Cart {
+            simulation
+                .tracks
+                .insert((x, y), if intersection { '+' } else { new_c });
+            simulation.start.push(Cart {
+                id: simulation.start.len(),
                 x,
                 y,
                 vx,
                 vy,
                 ..Default::default()
             });
-            self.occupied.insert((x, y));
+        } else {
+            simulation.tracks.insert((x, y), c);
         }
     }
 
     fn part01(&mut self) -> Location {
         loop {
-            let mut new_carts = BinaryHeap::new();
-            while let Some(mut cart) = self.carts.pop() {
-                self.occupied.remove(&(cart.x, cart.y));
-                cart.step();
-                if self.occupied.contains(&(cart.x, cart.y)) {
-                    return (cart.x, cart.y);
-                }
-                let track_section = self.tracks.get(&(cart.x, cart.y)).expect("❌");
-                match track_section {
-                    '|' | '-' => (),
-                    &track_section => cart.turn(track_section),
-                }
-                self.occupied.insert((cart.x, cart.y));
-                new_carts.push(cart);
+            self.simulation.tick();
+            if let Some(collision) = self.simulation.collisions.first() {
+                return collision.location;
             }
-            self.carts = new_carts;
         }
     }
 
     fn part02(&mut self) -> Location {
-        loop {
-            let mut new_carts = BinaryHeap::new();
-            while let Some(mut cart) = self.carts.pop() {
-                if !self.occupied.contains(&(cart.x, cart.y)) {
-                    continue;
-                }
-                self.occupied.remove(&(cart.x, cart.y));
-                cart.step();
-                if self.occupied.is_empty() {
-                    return (cart.x, cart.y);
-                }
-                let track_section = self.tracks.get(&(cart.x, cart.y)).expect("❌");
-                match track_section {
-                    '|' | '-' => (),
-                    &track_section => cart.turn(track_section),
-                }
-                if self.occupied.contains(&(cart.x, cart.y)) {
-                    self.occupied.remove(&(cart.x, cart.y));
-                    continue;
+        while self.simulation.carts.len() > 1 {
+            self.simulation.tick();
+        }
+        self.simulation.positions().first().expect("❌").1
+    }
+}
+
+impl Simulation {
+    /// Moves every cart once, in reading order of their locations, removing the ones that crash
+    pub fn tick(&mut self) -> Vec<Event> {
+        self.ticks += 1;
+        self.carts.sort_by_key(|c| (c.y, c.x));
+        let mut occupied: HashMap<Location, usize> =
+            self.carts.iter().map(|c| ((c.x, c.y), c.id)).collect();
+        let mut crashed = HashSet::new();
+        let mut events = vec![];
+        for cart in &mut self.carts {
+            if crashed.contains(&cart.id) {
+                continue;
+            }
+            let from = (cart.x, cart.y);
+            occupied.remove(&from);
+            cart.step();
+            let to = (cart.x, cart.y);
+            events.push(Event::Moved {
+                cart: cart.id,
+                from,
+                to,
+            });
+            if let Some(other) = occupied.remove(&to) {
+                let collision = Collision {
+                    tick: self.ticks,
+                    location: to,
+                    carts: [cart.id, other],
+                };
+                self.collisions.push(collision);
+                events.push(Event::Crashed(collision));
+                for id in collision.carts {
+                    crashed.insert(id);
+                    events.push(Event::Removed {
+                        cart: id,
+                        location: to,
+                    });
                 }
-                self.occupied.insert((cart.x, cart.y));
-                new_carts.push(cart);
+                continue;
+            }
+            match self.tracks.get(&to).expect("❌") {
+                '|' | '-' => (),
+                &track_section => cart.turn(track_section),
             }
-            self.carts = new_carts;
+            occupied.insert(to, cart.id);
+        }
+        self.carts.retain(|c| !crashed.contains(&c.id));
+        events
+    }
+
+    /// Ticks run so far
+    pub fn ticks(&self) -> usize {
+        self.ticks
+    }
+
+    pub fn collisions(&self) -> &[Collision] {
+        &self.collisions
+    }
+
+    /// Carts still on the tracks and their locations, in reading order of the locations
+    pub fn positions(&self) -> Vec<(usize, Location)> {
+        let mut positions: Vec<_> = self.carts.iter().map(|c| (c.id, (c.x, c.y))).collect();
+        positions.sort_by_key(|&(_, (x, y))| (y, x));
+        positions
+    }
+
+    /// Positions once `tick` ticks have been run, replaying from the start for past ticks
+    pub fn positions_at(&self, tick: usize) -> Vec<(usize, Location)> {
+        self.replayed(tick).positions()
+    }
+
+    /// Tracks with the carts on them and an `X` where carts crashed during the last tick, like
+    /// the puzzle diagrams
+    pub fn render(&self) -> String {
+        let width = self.tracks.keys().map(|&(x, _)| x + 1).max().unwrap_or(0);
+        let height = self.tracks.keys().map(|&(_, y)| y + 1).max().unwrap_or(0);
+        let mut grid = vec![vec![' '; width]; height];
+        for (&(x, y), &c) in &self.tracks {
+            grid[y][x] = c;
+        }
+        for cart in &self.carts {
+            grid[cart.y][cart.x] = cart.glyph();
+        }
+        for collision in self.collisions.iter().filter(|c| c.tick == self.ticks) {
+            let (x, y) = collision.location;
+            grid[y][x] = 'X';
         }
+        grid.iter()
+            .map(|row| row.iter().collect::<String>() + "\n")
+            .collect()
+    }
+
+    pub fn render_at(&self, tick: usize) -> String {
+        self.replayed(tick).render()
+    }
+
+    fn replayed(&self, tick: usize) -> Simulation {
+        let mut simulation = self.clone();
+        if tick < self.ticks {
+            simulation.carts = self.start.clone();
+            simulation.ticks = 0;
+            simulation.collisions.clear();
+        }
+        while simulation.ticks < tick {
+            simulation.tick();
+        }
+        simulation
     }
 }
 
 impl Cart {
+    fn glyph(&self) -> char {
+        match (self.vx, self.vy) {
+            NORTH => '^',
+            SOUTH => 'v',
+            WEST => '<',
+            _ => '>',
+        }
+    }
+
     fn step(&mut self) {
         self.x = (self.x as isize + self.vx) as usize;
         self.y = (self.y as isize + self.vy) as usize;
@@ -162,10 +312,103 @@ impl Cart {
     }
 }
 
+/// Sets of directions a glyph may lead to, curves depending on the corner they make and carts
+/// on the track under them
+fn connections(c: char) -> Vec<Vec<Direction>> {
+    match c {
+        '-' => vec![vec![WEST, EAST]],
+        '|' => vec![vec![NORTH, SOUTH]],
+        '+' => vec![ALL_DIRECTIONS.to_vec()],
+        '/' => vec![vec![EAST, SOUTH], vec![WEST, NORTH]],
+        '\\' => vec![vec![WEST, SOUTH], vec![EAST, NORTH]],
+        '<' | '>' => vec![ALL_DIRECTIONS.to_vec(), vec![WEST, EAST]],
+        '^' | 'v' => vec![ALL_DIRECTIONS.to_vec(), vec![NORTH, SOUTH]],
+        _ => vec![],
+    }
+}
+
+/// Drops the connections a glyph can't have given its neighbours until none can be dropped: a
+/// curve turns away from the tracks that don't lead to it, and a cart is on an intersection only
+/// if tracks lead to it from all four sides. Glyphs left without any connection keep theirs, so
+/// that the error points at them rather than at their neighbours
+fn narrow(options: &mut HashMap<Location, Vec<Vec<Direction>>>) {
+    loop {
+        let ambiguous: Vec<_> = options
+            .iter()
+            .filter(|(_, o)| o.len() > 1)
+            .map(|(&l, _)| l)
+            .collect();
+        let mut narrowed = false;
+        for location in ambiguous {
+            let kept: Vec<_> = options[&location]
+                .iter()
+                .filter(|directions| {
+                    ALL_DIRECTIONS
+                        .iter()
+                        .all(|&d| match link(options, location, d) {
+                            Some(linked) => linked == directions.contains(&d),
+                            None => true,
+                        })
+                })
+                .cloned()
+                .collect();
+            if !kept.is_empty() && kept.len() < options[&location].len() {
+                options.insert(location, kept);
+                narrowed = true;
+            }
+        }
+        if !narrowed {
+            return;
+        }
+    }
+}
+
+/// Whether the glyph next to `location` in `direction` leads back to it, `None` while its
+/// connections are still ambiguous
+fn link(
+    options: &HashMap<Location, Vec<Vec<Direction>>>,
+    location: Location,
+    (dx, dy): Direction,
+) -> Option<bool> {
+    let sets = match neighbor(location, (dx, dy)).and_then(|n| options.get(&n)) {
+        Some(sets) => sets,
+        None => return Some(false),
+    };
+    match sets.iter().filter(|d| d.contains(&(-dx, -dy))).count() {
+        0 => Some(false),
+        back if back == sets.len() => Some(true),
+        _ => None,
+    }
+}
+
+fn neighbor((x, y): Location, (dx, dy): Direction) -> Option<Location> {
+    Some((x.checked_add_signed(dx)?, y.checked_add_signed(dy)?))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const EXAMPLE_01: [&str; 6] = [
+        r"/->-\        ",
+        r"|   |  /----\",
+        r"| /-+--+-\  |",
+        r"| | |  | v  |",
+        r"\-+-/  \-+--/",
+        r"  \------/   ",
+    ];
+
+    #[rustfmt::skip]
+    const EXAMPLE_02: [&str; 7] = [
+        r"/>-<\  ",
+        r"|   |  ",
+        r"| /<+-\",
+        r"| | | v",
+        r"\>+</ |",
+        r"  |   ^",
+        r"  \<->/",
+    ];
+
     macro_rules! test_parts {
         ($($name:ident: $values:expr,)*) => {
             $(
@@ -179,22 +422,171 @@ mod tests {
     }
 
     test_parts! {
-        test_part01_01: (part01, vec![
-            r"/->-\        ",
-            r"|   |  /----\",
-            r"| /-+--+-\  |",
-            r"| | |  | v  |",
-            r"\-+-/  \-+--/",
-            r"  \------/   ",
-        ], "7,3"),
-        test_part02_01: (part02, vec![
-            r"/>-<\  ",
-            r"|   |  ",
-            r"| /<+-\",
-            r"| | | v",
-            r"\>+</ |",
-            r"  |   ^",
-            r"  \<->/",
-        ], "6,4"),
+        test_part01_01: (part01, EXAMPLE_01.to_vec(), "7,3"),
+        test_part02_01: (part02, EXAMPLE_02.to_vec(), "6,4"),
+    }
+
+    #[test]
+    fn test_tick_events() {
+        let mut simulation = simulation(&[r"/>-<\", r"\---/"]).unwrap();
+        assert_eq!(
+            simulation.tick(),
+            vec![
+                Event::Moved {
+                    cart: 0,
+                    from: (1, 0),
+                    to: (2, 0),
+                },
+                Event::Moved {
+                    cart: 1,
+                    from: (3, 0),
+                    to: (2, 0),
+                },
+                Event::Crashed(Collision {
+                    tick: 1,
+                    location: (2, 0),
+                    carts: [1, 0],
+                }),
+                Event::Removed {
+                    cart: 1,
+                    location: (2, 0),
+                },
+                Event::Removed {
+                    cart: 0,
+                    location: (2, 0),
+                },
+            ],
+        );
+        assert_eq!(simulation.positions(), vec![]);
+        assert_eq!(simulation.tick(), vec![]);
+        assert_eq!(simulation.ticks(), 2);
+    }
+
+    #[test]
+    fn test_collision_log() {
+        let mut simulation = simulation(&EXAMPLE_01).unwrap();
+        while simulation.collisions().is_empty() {
+            simulation.tick();
+        }
+        assert_eq!(
+            simulation.collisions(),
+            [Collision {
+                tick: 14,
+                location: (7, 3),
+                carts: [0, 1],
+            }],
+        );
+        let mut last_cart = super::simulation(&EXAMPLE_02).unwrap();
+        while last_cart.positions().len() > 1 {
+            last_cart.tick();
+        }
+        let crashes: Vec<_> = last_cart
+            .collisions()
+            .iter()
+            .map(|c| (c.tick, c.location))
+            .collect();
+        assert_eq!(
+            crashes,
+            [(1, (2, 0)), (1, (2, 4)), (1, (6, 4)), (3, (2, 4))]
+        );
+        assert_eq!(last_cart.positions(), [(8, (6, 4))]);
+    }
+
+    #[test]
+    fn test_positions_at() {
+        let mut simulation = simulation(&EXAMPLE_01).unwrap();
+        assert_eq!(simulation.positions_at(0), vec![(0, (2, 0)), (1, (9, 3))]);
+        assert_eq!(simulation.positions_at(2), vec![(0, (4, 0)), (1, (10, 4))]);
+        for _ in 0..5 {
+            simulation.tick();
+        }
+        assert_eq!(simulation.positions_at(2), vec![(0, (4, 0)), (1, (10, 4))]);
+        assert_eq!(simulation.positions_at(13), vec![(1, (7, 2)), (0, (7, 4))]);
+        assert_eq!(simulation.positions_at(14), vec![]);
+        assert_eq!(simulation.ticks(), 5);
+    }
+
+    #[test]
+    fn test_render() {
+        let simulation = simulation(&EXAMPLE_01).unwrap();
+        assert_eq!(
+            simulation.render(),
+            EXAMPLE_01.map(|l| l.to_string() + "\n").concat()
+        );
+        assert_eq!(
+            simulation.render_at(13),
+            [
+                r"/---\        ",
+                r"|   |  /----\",
+                r"| /-+--v-\  |",
+                r"| | |  | |  |",
+                r"\-+-/  ^-+--/",
+                r"  \------/   ",
+            ]
+            .map(|l| l.to_string() + "\n")
+            .concat(),
+        );
+        assert_eq!(
+            simulation.render_at(14),
+            [
+                r"/---\        ",
+                r"|   |  /----\",
+                r"| /-+--+-\  |",
+                r"| | |  X |  |",
+                r"\-+-/  \-+--/",
+                r"  \------/   ",
+            ]
+            .map(|l| l.to_string() + "\n")
+            .concat(),
+        );
+    }
+
+    #[test]
+    fn test_cart_on_intersection() {
+        let simulation = simulation(&[
+            r"/---\  ", r"|   |  ", r"| /-v-\", r"| | | |", r"\-+-/ |", r"  |   |", r"  \---/",
+        ])
+        .unwrap();
+        assert_eq!(simulation.tracks[&(4, 2)], '+');
+        assert_eq!(simulation.positions_at(1), [(0, (4, 3))]);
+    }
+
+    #[test]
+    fn test_cart_between_curves() {
+        let simulation = simulation(&[
+            r"/---\    ",
+            r"|   |    ",
+            r"\---/    ",
+            r"  /->--\ ",
+            r"/-+-\  | ",
+            r"| | |  | ",
+            r"\-+-/  | ",
+            r"  \----/ ",
+        ])
+        .unwrap();
+        assert_eq!(simulation.tracks[&(4, 3)], '-');
+        assert_eq!(simulation.positions_at(3), [(0, (7, 3))]);
+        assert_eq!(simulation.positions_at(4), [(0, (7, 4))]);
+    }
+
+    macro_rules! test_read_from_errors {
+        ($($name:ident: $values:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (lines, expected): (Vec<&str>, &str) = $values;
+                    assert_eq!(Day::read_from(&lines).map(|_| ()), Err(expected.to_string()));
+                }
+            )*
+        }
+    }
+
+    test_read_from_errors! {
+        test_read_from_errors_01: (vec![r"/-\", r"|#|", r"\-/"], "unknown glyph `#` at 1,1"),
+        test_read_from_errors_02: (vec![r"/--", r"| |", r"\-/"], "track `-` at 2,0 has a dangling end"),
+        test_read_from_errors_03: (vec![r"/-\", r"| /", r"\-/"], "track `/` at 2,1 has a dangling end"),
+        test_read_from_errors_04: (vec![r"/-\", r"> |", r"\-/"], "cart at 0,1 is not on a straight track or an intersection"),
+        test_read_from_errors_05: (vec![r">-\", r"| |", r"\-/"], "cart at 0,0 is not on a straight track or an intersection"),
+        test_read_from_errors_06: (vec![r"/-\  ", r"| | v", r"\-/  "], "cart at 4,1 is not on a straight track or an intersection"),
     }
 }
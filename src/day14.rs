@@ -1,6 +1,18 @@
 /// Day 14 (https://adventofcode.com/2018/day/14)
 extern crate text_io;
 
+use std::env;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+const STARTING_SCORES: [u8; 2] = [3, 7];
+const ELVES: usize = 2;
+const SCORES_AFTER: usize = 10;
+const CACHE_LEN: usize = 1 << 21;
+const CACHE_HEADER_LEN: u64 = 8;
+
 pub fn part01<T: AsRef<str>>(lines: &[T]) -> String {
     Day::read_from(lines)
         .part01()
@@ -14,10 +26,75 @@ pub fn part02<T: AsRef<str>>(lines: &[T]) -> usize {
     Day::read_from(lines).part02()
 }
 
+/// Like `part01`, reading the scores from the cache in `dir`
+pub fn part01_cached<T: AsRef<str>>(lines: &[T], dir: &Path) -> io::Result<String> {
+    let day = Day::read_from(lines);
+    let len = CACHE_LEN.max(day.recipes + SCORES_AFTER);
+    let cache = CachedScores::open(dir, &STARTING_SCORES, ELVES, len)?;
+    let scores = cache.scores(day.recipes, SCORES_AFTER)?.expect("❌");
+    Ok(scores.iter().map(|score| score.to_string()).collect())
+}
+
+/// Like `part02`, searching the scores in the cache in `dir`, which is regenerated twice as long
+/// until it holds the digits
+pub fn part02_cached<T: AsRef<str>>(lines: &[T], dir: &Path) -> io::Result<usize> {
+    let day = Day::read_from(lines);
+    let mut len = CACHE_LEN;
+    loop {
+        let cache = CachedScores::open(dir, &STARTING_SCORES, ELVES, len)?;
+        if let Some(recipes) = cache.find(&day.digits)? {
+            return Ok(recipes);
+        }
+        len = cache.len * 2;
+    }
+}
+
+/// `$XDG_CACHE_HOME/advent_of_code_2018`, falling back to `~/.cache` and then to the temporary
+/// directory
+pub fn default_cache_dir() -> PathBuf {
+    cache_dir(env::var_os("XDG_CACHE_HOME"), env::var_os("HOME"))
+}
+
+/// Like the XDG spec, ignores empty or relative values, which would put the cache wherever the
+/// puzzle happens to be run from
+fn cache_dir(xdg_cache_home: Option<OsString>, home: Option<OsString>) -> PathBuf {
+    let absolute = |dir: &PathBuf| dir.is_absolute();
+    xdg_cache_home
+        .map(PathBuf::from)
+        .filter(absolute)
+        .or_else(|| {
+            home.map(|home| PathBuf::from(home).join(".cache"))
+                .filter(absolute)
+        })
+        .unwrap_or_else(env::temp_dir)
+        .join("advent_of_code_2018")
+}
+
 #[derive(Debug, Default)]
 struct Day {
-    recipes: Vec<usize>,
-    target_len: usize,
+    digits: Vec<u8>,
+    recipes: usize,
+}
+
+/// Recipe scores the elves create by combining their current recipes
+#[derive(Clone, Debug, PartialEq)]
+pub struct Scoreboard {
+    scores: Vec<u8>,
+    elves: Vec<usize>,
+}
+
+/// Scores saved to a file named after the starting scores and number of elves, as the number of
+/// scores (a little-endian `u64`) followed by the scores two per byte, high nibble first
+#[derive(Clone, Debug, PartialEq)]
+pub struct CachedScores {
+    path: PathBuf,
+    len: usize,
+}
+
+/// Knuth-Morris-Pratt matcher fed one score at a time
+struct Matcher<'a> {
+    digits: &'a [u8],
+    fallbacks: Vec<usize>,
     matched: usize,
 }
 
@@ -25,59 +102,223 @@ impl Day {
     fn read_from<T: AsRef<str>>(lines: &[T]) -> Self {
         let first_line = lines.first().expect("❌").as_ref();
         Day {
-            recipes: first_line
+            digits: first_line
                 .chars()
-                .map(|c| c.to_digit(10).expect("❌") as usize)
+                .map(|c| c.to_digit(10).expect("❌") as u8)
                 .collect(),
-            target_len: first_line.parse::<usize>().expect("❌") + 10,
-            matched: 0,
+            recipes: first_line.parse().expect("❌"),
         }
     }
 
-    fn part01(&mut self) -> Vec<usize> {
-        self.create_new_recipes_until(|s, r, _| r.len() >= s.target_len)[self.target_len - 10..]
-            .to_vec()
+    fn part01(&self) -> Vec<u8> {
+        let mut scoreboard = Scoreboard::new(&STARTING_SCORES, ELVES).expect("❌");
+        scoreboard.extend_to(self.recipes + SCORES_AFTER);
+        scoreboard.scores()[self.recipes..self.recipes + SCORES_AFTER].to_vec()
     }
 
-    fn part02(&mut self) -> usize {
-        self.create_new_recipes_until(Day::has_match).len() - self.recipes.len()
+    fn part02(&self) -> usize {
+        let mut scoreboard = Scoreboard::new(&STARTING_SCORES, ELVES).expect("❌");
+        scoreboard.find(&self.digits)
     }
+}
 
-    fn create_new_recipes_until<F>(&mut self, predicate: F) -> Vec<usize>
-    where
-        F: Fn(&mut Day, &Vec<usize>, usize) -> bool,
-    {
-        let mut recipes = vec![3, 7];
-        let (mut elf1, mut elf2) = (0, 1);
+impl Scoreboard {
+    /// Scoreboard starting with the `starting` scores, the elves being on the first ones
+    pub fn new(starting: &[u8], elves: usize) -> Result<Self, String> {
+        if let Some(score) = starting.iter().find(|&&score| score > 9) {
+            return Err(format!("score {} is not a single digit", score));
+        }
+        if elves == 0 || elves > starting.len() {
+            return Err(format!(
+                "{} elves cannot start on {} recipes",
+                elves,
+                starting.len()
+            ));
+        }
+        Ok(Scoreboard {
+            scores: starting.to_vec(),
+            elves: (0..elves).collect(),
+        })
+    }
+
+    pub fn scores(&self) -> &[u8] {
+        &self.scores
+    }
+
+    /// Creates recipes until there are at least `len` of them
+    pub fn extend_to(&mut self, len: usize) {
+        while self.scores.len() < len {
+            self.create_recipes();
+        }
+    }
+
+    /// Number of recipes left of the first occurrence of `digits`, matched against the scores
+    /// as they get created
+    pub fn find(&mut self, digits: &[u8]) -> usize {
+        if digits.is_empty() {
+            return 0;
+        }
+        let mut matcher = Matcher::new(digits);
+        let mut index = 0;
         loop {
-            let new_recipe = recipes[elf1] + recipes[elf2];
-            if new_recipe >= 10 {
-                recipes.push(new_recipe / 10);
-                if predicate(self, &recipes, new_recipe / 10) {
-                    break;
-                }
+            self.extend_to(index + 1);
+            index += 1;
+            if matcher.push(self.scores[index - 1]) {
+                return index - digits.len();
             }
-            recipes.push(new_recipe % 10);
-            if predicate(self, &recipes, new_recipe % 10) {
+        }
+    }
+
+    /// Appends the digits of the sum of the elves' current scores, then moves each elf forward
+    /// by one plus its current score
+    fn create_recipes(&mut self) {
+        let mut sum: usize = self.elves.iter().map(|&e| self.scores[e] as usize).sum();
+        let start = self.scores.len();
+        loop {
+            self.scores.push((sum % 10) as u8);
+            sum /= 10;
+            if sum == 0 {
                 break;
             }
-            elf1 = (elf1 + 1 + recipes[elf1]) % recipes.len();
-            elf2 = (elf2 + 1 + recipes[elf2]) % recipes.len();
         }
-        recipes
+        self.scores[start..].reverse();
+        for elf in &mut self.elves {
+            *elf = (*elf + 1 + self.scores[*elf] as usize) % self.scores.len();
+        }
+    }
+}
+
+impl CachedScores {
+    /// Opens the cache for the given scoreboard parameters in `dir`, first generating and saving
+    /// `len` scores when the cache is missing or shorter
+    pub fn open(dir: &Path, starting: &[u8], elves: usize, len: usize) -> io::Result<Self> {
+        let name: String = starting.iter().map(|score| score.to_string()).collect();
+        let path = dir.join(format!("scores-{}-{}.bin", name, elves));
+        if let Ok(cache) = CachedScores::read_header(&path) {
+            if cache.len >= len {
+                return Ok(cache);
+            }
+        }
+        let mut scoreboard = Scoreboard::new(starting, elves)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        scoreboard.extend_to(len);
+        fs::create_dir_all(dir)?;
+        // Written aside first so an interrupted run never leaves a truncated cache behind
+        let partial = path.with_extension("partial");
+        let mut writer = BufWriter::new(File::create(&partial)?);
+        writer.write_all(&(scoreboard.scores.len() as u64).to_le_bytes())?;
+        for pair in scoreboard.scores.chunks(2) {
+            writer.write_all(&[pair[0] << 4 | pair.get(1).unwrap_or(&0)])?;
+        }
+        writer.flush()?;
+        fs::rename(&partial, &path)?;
+        Ok(CachedScores {
+            path,
+            len: scoreboard.scores.len(),
+        })
+    }
+
+    fn read_header(path: &Path) -> io::Result<Self> {
+        let mut header = [0; CACHE_HEADER_LEN as usize];
+        File::open(path)?.read_exact(&mut header)?;
+        Ok(CachedScores {
+            path: path.to_path_buf(),
+            len: u64::from_le_bytes(header) as usize,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The `count` scores following the first `start` ones, if the cache holds them
+    pub fn scores(&self, start: usize, count: usize) -> io::Result<Option<Vec<u8>>> {
+        if start + count > self.len {
+            return Ok(None);
+        }
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(CACHE_HEADER_LEN + start as u64 / 2))?;
+        let mut bytes = vec![0; (start % 2 + count).div_ceil(2)];
+        file.read_exact(&mut bytes)?;
+        Ok(Some(
+            bytes
+                .iter()
+                .flat_map(|byte| vec![byte >> 4, byte & 0xf])
+                .skip(start % 2)
+                .take(count)
+                .collect(),
+        ))
     }
 
-    fn has_match(&mut self, _: &Vec<usize>, recipe: usize) -> bool {
-        if self.matched < self.recipes.len() && self.recipes[self.matched] == recipe {
+    /// Number of recipes left of the first occurrence of `digits`, if the cache holds it
+    pub fn find(&self, digits: &[u8]) -> io::Result<Option<usize>> {
+        if digits.is_empty() {
+            return Ok(Some(0));
+        }
+        let mut matcher = Matcher::new(digits);
+        let mut reader = BufReader::new(File::open(&self.path)?);
+        reader.seek(SeekFrom::Start(CACHE_HEADER_LEN))?;
+        let mut index = 0;
+        for byte in reader.bytes() {
+            let byte = byte?;
+            for score in [byte >> 4, byte & 0xf] {
+                if index == self.len {
+                    return Ok(None);
+                }
+                index += 1;
+                if matcher.push(score) {
+                    return Ok(Some(index - digits.len()));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<'a> Matcher<'a> {
+    fn new(digits: &'a [u8]) -> Self {
+        Matcher {
+            digits,
+            fallbacks: fallbacks(digits),
+            matched: 0,
+        }
+    }
+
+    /// Feeds the next score, telling whether it completes a match, falling back to the longest
+    /// partial match still going when it does not match
+    fn push(&mut self, score: u8) -> bool {
+        if self.matched == self.digits.len() {
+            self.matched = self.fallbacks[self.matched - 1];
+        }
+        while self.matched > 0 && self.digits[self.matched] != score {
+            self.matched = self.fallbacks[self.matched - 1];
+        }
+        if self.digits[self.matched] == score {
             self.matched += 1;
-            return self.matched == self.recipes.len();
         }
-        self.matched = 0;
-        if self.recipes[0] == recipe {
-            self.matched = 1;
+        self.matched == self.digits.len()
+    }
+}
+
+/// Length of the longest proper prefix of `digits` that is also a suffix of `digits[..=i]`, for
+/// every `i`
+fn fallbacks(digits: &[u8]) -> Vec<usize> {
+    let mut fallbacks = vec![0; digits.len()];
+    let mut len = 0;
+    for i in 1..digits.len() {
+        while len > 0 && digits[i] != digits[len] {
+            len = fallbacks[len - 1];
         }
-        false
+        if digits[i] == digits[len] {
+            len += 1;
+        }
+        fallbacks[i] = len;
     }
+    fallbacks
 }
 
 #[cfg(test)]
@@ -105,5 +346,83 @@ mod tests {
         test_part02_02: (part02, vec!["01245"], 5),
         test_part02_03: (part02, vec!["92510"], 18),
         test_part02_04: (part02, vec!["59414"], 2018),
+        test_part02_05: (part02, vec!["1212"], 78),
+        test_part02_06: (part02, vec!["11011"], 1241),
+        test_part02_07: (part02, vec!["37"], 0),
+    }
+
+    #[test]
+    fn test_fallbacks() {
+        assert_eq!(fallbacks(&[1, 2, 1, 2]), [0, 0, 1, 2]);
+        assert_eq!(fallbacks(&[1, 1, 0, 1, 1]), [0, 1, 0, 1, 2]);
+        assert_eq!(fallbacks(&[5, 1, 5, 8, 9]), [0, 0, 1, 0, 0]);
+    }
+
+    #[test]
+    fn test_cache_dir() {
+        let dir = |xdg: Option<&str>, home: Option<&str>| {
+            cache_dir(xdg.map(OsString::from), home.map(OsString::from))
+        };
+        assert_eq!(
+            dir(Some("/xdg"), Some("/home/elf")),
+            Path::new("/xdg/advent_of_code_2018")
+        );
+        assert_eq!(
+            dir(Some(""), Some("/home/elf")),
+            Path::new("/home/elf/.cache/advent_of_code_2018")
+        );
+        assert_eq!(
+            dir(Some("cache"), None),
+            env::temp_dir().join("advent_of_code_2018")
+        );
+        assert_eq!(
+            dir(None, Some("")),
+            env::temp_dir().join("advent_of_code_2018")
+        );
+    }
+
+    #[test]
+    fn test_cached_scores() {
+        let dir = env::temp_dir().join(format!("day14-cache-{}", std::process::id()));
+        let cache = CachedScores::open(&dir, &[0, 1, 2], 3, 19).unwrap();
+        assert_eq!(cache.path, dir.join("scores-012-3.bin"));
+        assert_eq!(cache.len(), 19);
+        assert_eq!(fs::metadata(&cache.path).unwrap().len(), 8 + 10);
+        assert_eq!(cache.scores(3, 5).unwrap(), Some(vec![3, 5, 8, 1, 0]));
+        assert_eq!(
+            cache.scores(8, 11).unwrap(),
+            Some(vec![7, 7, 1, 5, 1, 7, 4, 1, 0, 1, 2])
+        );
+        assert_eq!(cache.scores(8, 12).unwrap(), None);
+        assert_eq!(cache.find(&[7, 7, 1]).unwrap(), Some(8));
+        assert_eq!(cache.find(&[0, 1, 2]).unwrap(), Some(0));
+        assert_eq!(cache.find(&[1, 2, 0]).unwrap(), None);
+        assert_eq!(CachedScores::open(&dir, &[0, 1, 2], 3, 10).unwrap(), cache);
+        let longer = CachedScores::open(&dir, &[0, 1, 2], 3, 20).unwrap();
+        assert_eq!(longer.len(), 20);
+        assert_eq!(longer.find(&[1, 2, 0]).unwrap(), Some(17));
+        assert_eq!(part01_cached(&["2018"], &dir).unwrap(), "5941429882");
+        assert_eq!(part02_cached(&["59414"], &dir).unwrap(), 2018);
+        assert_eq!(part02_cached(&["11011"], &dir).unwrap(), 1241);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_scoreboard() {
+        let mut scoreboard = Scoreboard::new(&[0, 1, 2], 3).unwrap();
+        scoreboard.extend_to(20);
+        assert_eq!(
+            scoreboard.scores(),
+            [0, 1, 2, 3, 5, 8, 1, 0, 7, 7, 1, 5, 1, 7, 4, 1, 0, 1, 2, 0]
+        );
+        assert_eq!(scoreboard.find(&[7, 7, 1]), 8);
+        assert_eq!(
+            Scoreboard::new(&[3, 7], 3),
+            Err("3 elves cannot start on 2 recipes".to_string())
+        );
+        assert_eq!(
+            Scoreboard::new(&[3, 17], 2),
+            Err("score 17 is not a single digit".to_string())
+        );
     }
 }
@@ -13,6 +13,52 @@ pub fn part02<T: AsRef<str>>(lines: &[T]) -> isize {
     Day::read_from(lines).part02()
 }
 
+/// Lowest attack power letting the elves win without losses
+pub fn winning_elf_attack_power<T: AsRef<str>>(lines: &[T]) -> isize {
+    Day::read_from(lines).weakest_winning_elves().0
+}
+
+/// The map after each round, as printed in the puzzle text
+pub fn replay<T: AsRef<str>>(lines: &[T], options: &ReplayOptions) -> Vec<String> {
+    let mut game = Game::read_from(lines);
+    game.elf_attack_power = options.elf_attack_power;
+    let mut frames = vec![format!("Initially:\n{}", game.render(options.ansi))];
+    let mut rounds = 0;
+    while game.elves > 0 && game.goblins > 0 && options.until.is_none_or(|until| rounds < until) {
+        let heading = if game.round() {
+            rounds += 1;
+            format!(
+                "After {} round{}:",
+                rounds,
+                if rounds == 1 { "" } else { "s" }
+            )
+        } else {
+            format!("Combat ends during round {}:", rounds + 1)
+        };
+        frames.push(format!("{}\n{}", heading, game.render(options.ansi)));
+    }
+    frames
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplayOptions {
+    /// Round after which the replay stops, if before the end of combat
+    pub until: Option<usize>,
+    /// Colours elves in green and goblins in red with ANSI escape codes
+    pub ansi: bool,
+    pub elf_attack_power: isize,
+}
+
+impl Default for ReplayOptions {
+    fn default() -> Self {
+        ReplayOptions {
+            until: None,
+            ansi: false,
+            elf_attack_power: ATTACK_POWER,
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 struct Day {
     game: Game,
@@ -26,6 +72,8 @@ struct Game {
     elves: usize,
     goblins: usize,
     elf_attack_power: isize,
+    width: usize,
+    height: usize,
 }
 
 #[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
@@ -56,11 +104,16 @@ impl Day {
     }
 
     fn part01(&mut self) -> isize {
-        self.game.elf_attack_power = 3;
+        self.game.elf_attack_power = ATTACK_POWER;
         self.game.play()
     }
 
     fn part02(&self) -> isize {
+        self.weakest_winning_elves().1
+    }
+
+    /// Lowest elf attack power with which no elf dies, and the outcome of that combat
+    fn weakest_winning_elves(&self) -> (isize, isize) {
         let mut min_outcome = 0;
         let mut min_power = 50;
         let (mut power_lo, mut power_hi) = (4, min_power);
@@ -79,14 +132,18 @@ impl Day {
                 power_lo = power + 1;
             }
         }
-        min_outcome
+        (min_power, min_outcome)
     }
 }
 
 impl Game {
     fn read_from<T: AsRef<str>>(lines: &[T]) -> Self {
-        let mut game = Game::default();
+        let mut game = Game {
+            height: lines.len(),
+            ..Game::default()
+        };
         for (y, line) in lines.iter().enumerate() {
+            game.width = game.width.max(line.as_ref().len());
             for (x, c) in line.as_ref().chars().enumerate() {
                 if c != '#' {
                     game.add_element(x, y, c);
@@ -117,29 +174,66 @@ impl Game {
     fn play(&mut self) -> isize {
         let mut rounds = 0;
         while self.elves > 0 && self.goblins > 0 {
-            let mut new_order: BinaryHeap<Position> = BinaryHeap::new();
-            while !self.order.is_empty() && self.elves > 0 && self.goblins > 0 {
-                let position = self.order.pop().expect("❌");
-                let &(kind, hp) = self.units.get(&position).expect("❌");
-                if let Some((new_pos, enemy_pos, attack)) = self.turn(position, kind) {
-                    if attack && self.attack(enemy_pos) {
-                        new_order.retain(|p| p != &enemy_pos);
-                    }
-                    if new_pos != position {
-                        self.units.remove(&position);
-                        self.units.insert(new_pos, (kind, hp));
+            if self.round() {
+                rounds += 1;
+            }
+        }
+        rounds * self.units.values().map(|(_, hp)| hp).sum::<isize>()
+    }
+
+    /// Gives each unit its turn in reading order, telling whether every unit got it before
+    /// combat ended
+    fn round(&mut self) -> bool {
+        let mut new_order: BinaryHeap<Position> = BinaryHeap::new();
+        while !self.order.is_empty() && self.elves > 0 && self.goblins > 0 {
+            let position = self.order.pop().expect("❌");
+            let &(kind, hp) = self.units.get(&position).expect("❌");
+            if let Some((new_pos, enemy_pos, attack)) = self.turn(position, kind) {
+                if attack && self.attack(enemy_pos) {
+                    new_order.retain(|p| p != &enemy_pos);
+                }
+                if new_pos != position {
+                    self.units.remove(&position);
+                    self.units.insert(new_pos, (kind, hp));
+                }
+                new_order.push(new_pos);
+            } else {
+                new_order.push(position);
+            }
+        }
+        let completed = self.order.is_empty();
+        self.order = new_order;
+        completed
+    }
+
+    /// Map with each row followed by its units and their hit points, e.g. `#.GE.#   G(200), E(197)`
+    fn render(&self, ansi: bool) -> String {
+        let paint = |kind: char| match (ansi, kind) {
+            (true, 'E') => "\x1b[32mE\x1b[0m".to_string(),
+            (true, 'G') => "\x1b[31mG\x1b[0m".to_string(),
+            _ => kind.to_string(),
+        };
+        let mut map = String::new();
+        for y in 0..self.height as isize {
+            let mut units = vec![];
+            for x in 0..self.width as isize {
+                let position = Position { x, y };
+                match self.units.get(&position) {
+                    Some(&(kind, hp)) => {
+                        map.push_str(&paint(kind));
+                        units.push(format!("{}({})", paint(kind), hp));
                     }
-                    new_order.push(new_pos);
-                } else {
-                    new_order.push(position);
+                    None if self.open_squares.contains(&position) => map.push('.'),
+                    None => map.push('#'),
                 }
             }
-            if self.order.is_empty() {
-                rounds += 1;
+            if !units.is_empty() {
+                map.push_str("   ");
+                map.push_str(&units.join(", "));
             }
-            self.order = new_order;
+            map.push('\n');
         }
-        rounds * self.units.values().map(|(_, hp)| hp).sum::<isize>()
+        map
     }
 
     fn turn(&self, position: Position, kind: char) -> Option<(Position, Position, bool)> {
@@ -157,7 +251,7 @@ impl Game {
 
     fn aim(&self, position: Position, kind: char) -> Option<Position> {
         let mut closest_pos: Option<Position> = None;
-        let mut fewest_hp = isize::max_value();
+        let mut fewest_hp = isize::MAX;
         for (dx, dy) in DELTAS {
             let enemy_pos = Position {
                 x: position.x + dx,
@@ -217,7 +311,7 @@ impl Game {
 
     fn attack(&mut self, enemy_pos: Position) -> bool {
         if let Some(&(enemy_kind, enemy_hp)) = self.units.get(&enemy_pos) {
-            let mut power = 3;
+            let mut power = ATTACK_POWER;
             if enemy_kind == 'G' {
                 power = self.elf_attack_power;
             }
@@ -238,6 +332,7 @@ impl Game {
     }
 }
 
+const ATTACK_POWER: isize = 3;
 const DELTAS: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)]; // up, left, right, down
 
 #[cfg(test)]
@@ -361,4 +456,69 @@ mod tests {
             "#########",
         ], 1_140),
     }
+
+    #[test]
+    fn test_replay() {
+        let example = [
+            "#######", "#.G...#", "#...EG#", "#.#.#G#", "#..G#E#", "#.....#", "#######",
+        ];
+        let frames = replay(&example, &ReplayOptions::default());
+        assert_eq!(frames.len(), 48);
+        assert_eq!(
+            frames[1],
+            [
+                "After 1 round:",
+                "#######",
+                "#..G..#   G(200)",
+                "#...EG#   E(197), G(197)",
+                "#.#G#G#   G(200), G(197)",
+                "#...#E#   E(197)",
+                "#.....#",
+                "#######\n",
+            ]
+            .join("\n"),
+        );
+        assert_eq!(
+            frames[2],
+            [
+                "After 2 rounds:",
+                "#######",
+                "#...G.#   G(200)",
+                "#..GEG#   G(200), E(188), G(194)",
+                "#.#.#G#   G(194)",
+                "#...#E#   E(194)",
+                "#.....#",
+                "#######\n",
+            ]
+            .join("\n"),
+        );
+        let final_map = [
+            "#######",
+            "#G....#   G(200)",
+            "#.G...#   G(131)",
+            "#.#.#G#   G(59)",
+            "#...#.#",
+            "#....G#   G(200)",
+            "#######\n",
+        ]
+        .join("\n");
+        assert_eq!(frames[47], format!("After 47 rounds:\n{}", final_map));
+        let options = ReplayOptions {
+            until: Some(2),
+            ansi: true,
+            ..ReplayOptions::default()
+        };
+        let frames = replay(&example, &options);
+        assert_eq!(frames.len(), 3);
+        assert!(frames[2].contains("#..\x1b[31mG\x1b[0m\x1b[32mE\x1b[0m\x1b[31mG\x1b[0m#"));
+        assert!(frames[2].ends_with("   \x1b[32mE\x1b[0m(194)\n#.....#\n#######\n"));
+    }
+
+    #[test]
+    fn test_winning_elf_attack_power() {
+        let example = [
+            "#######", "#.G...#", "#...EG#", "#.#.#G#", "#..G#E#", "#.....#", "#######",
+        ];
+        assert_eq!(winning_elf_attack_power(&example), 15);
+    }
 }
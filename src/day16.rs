@@ -1,17 +1,32 @@
 /// Day 16 (https://adventofcode.com/2018/day/16)
 extern crate text_io;
 
-use std::collections::HashMap;
-use text_io::scan;
+use crate::input;
+
+use text_io::try_scan;
 
 const TOTAL_INSTRUCTIONS: usize = 16;
+const SAMPLE_LINES: usize = 3;
+const REGISTERS: usize = 4;
+/// Instructions reading their `a` operand from a register rather than taking it as a value
+const REGISTER_A: u16 = 0b1101_1001_1111_1111;
+/// Instructions reading their `b` operand from a register rather than taking it as a value
+const REGISTER_B: u16 = 0b1011_0100_0101_0101;
+const MNEMONICS: [&str; TOTAL_INSTRUCTIONS] = [
+    "addr", "addi", "mulr", "muli", "banr", "bani", "borr", "bori", "setr", "seti", "gtir", "gtri",
+    "gtrr", "eqir", "eqri", "eqrr",
+];
 
 pub fn part01<T: AsRef<str>>(lines: &[T]) -> isize {
-    Day::read_from(lines).part01()
+    Day::read_from(lines).expect("❌").part01()
 }
 
 pub fn part02<T: AsRef<str>>(lines: &[T]) -> isize {
-    Day::read_from(lines).part02()
+    Day::read_from(lines).expect("❌").part02()
+}
+
+pub fn opcode_table<T: AsRef<str>>(lines: &[T]) -> Result<OpcodeTable, String> {
+    Day::read_from(lines)?.opcode_table()
 }
 
 #[derive(Debug, Default, PartialEq)]
@@ -27,7 +42,7 @@ struct Sample {
     after: Register,
 }
 
-type Register = [isize; 4];
+type Register = [isize; REGISTERS];
 
 #[derive(Copy, Clone, Debug, Default, PartialEq)]
 struct Instruction {
@@ -37,19 +52,52 @@ struct Instruction {
     c: usize,
 }
 
+/// Maps the opcode numbers found in the input to the instructions they stand for
+#[derive(Debug, Default, PartialEq)]
+pub struct OpcodeTable {
+    /// Mnemonic of each opcode number
+    pub mnemonics: [&'static str; TOTAL_INSTRUCTIONS],
+    /// Opcode numbers the samples alone could not pin down to a single instruction
+    pub ambiguous: Vec<usize>,
+    /// Indexes of samples that contradict every candidate left for their opcode
+    pub contradictory: Vec<usize>,
+    instructions: [usize; TOTAL_INSTRUCTIONS],
+}
+
 impl Day {
-    fn read_from<T: AsRef<str>>(lines: &[T]) -> Self {
-        let split_index = find_split_index(lines);
-        Day {
-            samples: lines[..split_index]
-                .chunks(4)
-                .map(|l| parse_sample(l))
-                .collect(),
-            instructions: lines[split_index + 3..]
-                .iter()
-                .map(|l| parse_instruction(l.as_ref()))
-                .collect(),
+    fn read_from<T: AsRef<str>>(lines: &[T]) -> Result<Self, String> {
+        let mut day = Day::default();
+        let mut i = 0;
+        while i < lines.len() && lines[i].as_ref().starts_with("Before:") {
+            day.samples.push(parse_sample(&lines[i..], i)?);
+            i += SAMPLE_LINES;
+            match lines.get(i).map(|l| l.as_ref()) {
+                Some("") => i += 1,
+                None => {}
+                Some(_) => return Err(input::at_line(i, "expected a blank line after sample")),
+            }
         }
+        let separator_start = i;
+        while i < lines.len() && lines[i].as_ref().is_empty() {
+            i += 1;
+        }
+        if i == lines.len() {
+            if day.samples.is_empty() {
+                return Err("no samples or program found".to_string());
+            }
+            return Ok(day);
+        }
+        if !day.samples.is_empty() && i == separator_start {
+            return Err(input::at_line(i, "expected a sample or a blank separator"));
+        }
+        for (n, line) in lines.iter().enumerate().skip(i) {
+            if line.as_ref().is_empty() {
+                return Err(input::at_line(n, "unexpected blank line in program"));
+            }
+            let instruction = parse_instruction(line.as_ref()).map_err(|e| input::at_line(n, e))?;
+            day.instructions.push(instruction);
+        }
+        Ok(day)
     }
 
     fn part01(&self) -> isize {
@@ -57,7 +105,7 @@ impl Day {
         for sample in &self.samples {
             let mut total_matching_opcodes = 0;
             for i in 0..TOTAL_INSTRUCTIONS {
-                if run_instruction(i, sample.before, sample.instruction) == sample.after {
+                if matches(i, sample) {
                     total_matching_opcodes += 1;
                     if total_matching_opcodes == 3 {
                         matching_samples += 1;
@@ -70,62 +118,196 @@ impl Day {
     }
 
     fn part02(&self) -> isize {
-        let mut sample_to_opcode = HashMap::new();
-        let mut opcode_to_sample = HashMap::new();
-        for sample in &self.samples {
-            if sample_to_opcode.contains_key(&sample.instruction.opcode) {
-                continue;
+        let table = self.opcode_table().expect("❌");
+        self.run_program(&table).expect("❌")[0]
+    }
+
+    fn run_program(&self, table: &OpcodeTable) -> Result<Register, String> {
+        let mut register = [0; REGISTERS];
+        for (n, instruction) in self.instructions.iter().enumerate() {
+            let i = table.instructions[instruction.opcode];
+            if !registers_in_range(i, *instruction) {
+                return Err(format!(
+                    "instruction {} (`{} {} {} {}`) reads a register out of range",
+                    n + 1,
+                    MNEMONICS[i],
+                    instruction.a,
+                    instruction.b,
+                    instruction.c
+                ));
             }
-            let mut matching_opcode = 0;
-            let mut total_matching_opcodes = 0;
-            for i in 0..TOTAL_INSTRUCTIONS {
-                if opcode_to_sample.contains_key(&i) {
-                    continue;
-                }
-                if run_instruction(i, sample.before, sample.instruction) == sample.after {
-                    matching_opcode = i;
-                    total_matching_opcodes += 1;
+            register = run_instruction(i, register, *instruction);
+        }
+        Ok(register)
+    }
+
+    /// Deduces the opcodes from the instructions matching the most samples of each, so that a
+    /// few bad samples can't rule out the right instruction, then reports the samples that don't
+    /// match the instruction their opcode ends up with
+    fn opcode_table(&self) -> Result<OpcodeTable, String> {
+        let mut table = OpcodeTable::default();
+        let matching: Vec<_> = self.samples.iter().map(matching_instructions).collect();
+        let mut counts = [[0; TOTAL_INSTRUCTIONS]; TOTAL_INSTRUCTIONS];
+        for (sample, &set) in self.samples.iter().zip(&matching) {
+            for (i, count) in counts[sample.instruction.opcode].iter_mut().enumerate() {
+                if set & 1 << i != 0 {
+                    *count += 1;
                 }
             }
-            if total_matching_opcodes == 1 {
-                sample_to_opcode.insert(sample.instruction.opcode, matching_opcode);
-                opcode_to_sample.insert(matching_opcode, sample.instruction.opcode);
+        }
+        let mut candidates = [0; TOTAL_INSTRUCTIONS];
+        for (set, counts) in candidates.iter_mut().zip(&counts) {
+            let most = counts.iter().max().expect("❌");
+            *set = (0..TOTAL_INSTRUCTIONS)
+                .filter(|&i| counts[i] == *most)
+                .fold(0, |set, i| set | 1 << i);
+        }
+        propagate(&mut candidates);
+        for (opcode, &set) in candidates.iter().enumerate() {
+            if set.count_ones() > 1 {
+                table.ambiguous.push(opcode);
             }
         }
-        let mut register = [0; 4];
-        for instruction in &self.instructions {
-            let opcode = sample_to_opcode[&instruction.opcode];
-            register = run_instruction(opcode, register, *instruction);
+        table.instructions = assign_opcodes(&candidates).ok_or("no consistent opcode mapping")?;
+        for (opcode, &i) in table.instructions.iter().enumerate() {
+            table.mnemonics[opcode] = MNEMONICS[i];
         }
-        register[0]
+        for (index, (sample, &set)) in self.samples.iter().zip(&matching).enumerate() {
+            if set & 1 << table.instructions[sample.instruction.opcode] == 0 {
+                table.contradictory.push(index);
+            }
+        }
+        Ok(table)
     }
 }
 
-fn find_split_index<T: AsRef<str>>(lines: &[T]) -> usize {
-    let mut iter = lines.iter();
-    let mut last_line = iter.next().unwrap().as_ref();
-    for (i, line) in iter.enumerate() {
-        if line.as_ref() == last_line {
-            return i;
+fn matching_instructions(sample: &Sample) -> u16 {
+    (0..TOTAL_INSTRUCTIONS)
+        .filter(|&i| matches(i, sample))
+        .fold(0, |set, i| set | 1 << i)
+}
+
+/// Whether instruction `i` turns the registers before the sample into the ones after it, which
+/// it can't when it reads a register that doesn't exist
+fn matches(i: usize, sample: &Sample) -> bool {
+    registers_in_range(i, sample.instruction)
+        && run_instruction(i, sample.before, sample.instruction) == sample.after
+}
+
+/// Whether the operands instruction `i` reads from registers name existing ones
+fn registers_in_range(i: usize, instr: Instruction) -> bool {
+    let in_range = |operand: isize| (0..REGISTERS as isize).contains(&operand);
+    (REGISTER_A & 1 << i == 0 || in_range(instr.a))
+        && (REGISTER_B & 1 << i == 0 || in_range(instr.b))
+}
+
+fn propagate(candidates: &mut [u16; TOTAL_INSTRUCTIONS]) {
+    let mut resolved = 0;
+    loop {
+        let singles = candidates
+            .iter()
+            .filter(|set| set.count_ones() == 1)
+            .fold(0, |singles, set| singles | set);
+        if singles == resolved {
+            break;
+        }
+        resolved = singles;
+        for set in candidates.iter_mut() {
+            if set.count_ones() > 1 {
+                *set &= !resolved;
+            }
         }
-        last_line = line.as_ref();
     }
-    0
 }
 
-fn parse_sample<T: AsRef<str>>(slice: &[T]) -> Sample {
-    let mut s = Sample::default();
-    let mut it = slice.iter();
-    scan!(it.next().unwrap().as_ref().bytes() => "Before: [{}, {}, {}, {}]", s.before[0], s.before[1], s.before[2], s.before[3]);
-    scan!(it.next().unwrap().as_ref().bytes() => "{} {} {} {}", s.instruction.opcode, s.instruction.a, s.instruction.b, s.instruction.c);
-    scan!(it.next().unwrap().as_ref().bytes() => "After:  [{}, {}, {}, {}]", s.after[0], s.after[1], s.after[2], s.after[3]);
-    s
+fn assign_opcodes(candidates: &[u16; TOTAL_INSTRUCTIONS]) -> Option<[usize; TOTAL_INSTRUCTIONS]> {
+    let mut owner = [None; TOTAL_INSTRUCTIONS];
+    for opcode in 0..TOTAL_INSTRUCTIONS {
+        let mut visited = 0;
+        if !augment(opcode, candidates, &mut owner, &mut visited) {
+            return None;
+        }
+    }
+    let mut instructions = [0; TOTAL_INSTRUCTIONS];
+    for (i, opcode) in owner.iter().enumerate() {
+        instructions[opcode.expect("❌")] = i;
+    }
+    Some(instructions)
 }
 
-fn parse_instruction(line: &str) -> Instruction {
-    let mut instruction = Instruction::default();
-    scan!(line.bytes() => "{} {} {} {}", instruction.opcode, instruction.a, instruction.b, instruction.c);
-    instruction
+fn augment(
+    opcode: usize,
+    candidates: &[u16; TOTAL_INSTRUCTIONS],
+    owner: &mut [Option<usize>; TOTAL_INSTRUCTIONS],
+    visited: &mut u16,
+) -> bool {
+    for i in 0..TOTAL_INSTRUCTIONS {
+        if candidates[opcode] & 1 << i == 0 || *visited & 1 << i != 0 {
+            continue;
+        }
+        *visited |= 1 << i;
+        if owner[i].is_none_or(|other| augment(other, candidates, owner, visited)) {
+            owner[i] = Some(opcode);
+            return true;
+        }
+    }
+    false
+}
+
+fn parse_sample<T: AsRef<str>>(slice: &[T], start: usize) -> Result<Sample, String> {
+    if slice.len() < SAMPLE_LINES {
+        return Err(input::at_line(
+            start,
+            "sample needs Before, instruction and After lines",
+        ));
+    }
+    let line = |n: usize| slice[n].as_ref();
+    Ok(Sample {
+        before: parse_register(line(0), "Before: ").map_err(|e| input::at_line(start, e))?,
+        instruction: parse_instruction(line(1)).map_err(|e| input::at_line(start + 1, e))?,
+        after: parse_register(line(2), "After:  ").map_err(|e| input::at_line(start + 2, e))?,
+    })
+}
+
+fn parse_register(line: &str, label: &str) -> Result<Register, String> {
+    let values = line
+        .strip_prefix(label)
+        .and_then(|l| l.strip_prefix('['))
+        .and_then(|l| l.strip_suffix(']'))
+        .ok_or_else(|| format!("expected `{}[a, b, c, d]`, found `{}`", label, line))?;
+    let values = values
+        .split(", ")
+        .map(|v| v.parse::<isize>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("invalid register value in `{}`: {}", line, e))?;
+    if values.len() != REGISTERS {
+        return Err(format!(
+            "expected {} register values, found {}",
+            REGISTERS,
+            values.len()
+        ));
+    }
+    let mut register = Register::default();
+    register.copy_from_slice(&values);
+    Ok(register)
+}
+
+fn parse_instruction(line: &str) -> Result<Instruction, String> {
+    let mut i = Instruction::default();
+    let mut scan = || -> Result<(), text_io::Error> {
+        try_scan!(line.bytes() => "{} {} {} {}", i.opcode, i.a, i.b, i.c);
+        Ok(())
+    };
+    scan().map_err(|_| format!("malformed instruction `{}`", line))?;
+    if i.opcode >= TOTAL_INSTRUCTIONS {
+        return Err(format!("unknown opcode {}", i.opcode));
+    }
+    // `a` and `b` are values or registers depending on the instruction, unknown until the
+    // opcodes are deduced, but every instruction writes to register `c`
+    if i.c >= REGISTERS {
+        return Err(format!("register out of range in `{}`", line));
+    }
+    Ok(i)
 }
 
 #[rustfmt::skip]
@@ -170,7 +352,7 @@ mod tests {
                 "9 2 1 2",
                 "9 3 2 1",
             ]),
-            Day {
+            Ok(Day {
                 samples: vec![Sample {
                     before: [3, 2, 1, 1],
                     instruction: Instruction {
@@ -195,7 +377,7 @@ mod tests {
                         c: 1,
                     },
                 ],
-            },
+            }),
         );
     }
 
@@ -226,24 +408,117 @@ mod tests {
     }
 
     #[test]
-    fn test_find_split_index() {
+    fn test_immediate_operands() {
+        let sample = Sample {
+            before: [0, 0, 0, 0],
+            instruction: parse_instruction("9 7 0 1").unwrap(),
+            after: [0, 7, 0, 0],
+        };
+        assert_eq!(matching_instructions(&sample), 1 << 9);
+
+        let mut day = Day::read_from(&crate::input::read_lines_from_input("data/day16")).unwrap();
+        let table = day.opcode_table().unwrap();
+        let opcode = |mnemonic| table.mnemonics.iter().position(|&m| m == mnemonic).unwrap();
+        day.instructions = vec![Instruction {
+            opcode: opcode("seti"),
+            a: 7,
+            b: 0,
+            c: 1,
+        }];
+        assert_eq!(day.run_program(&table), Ok([0, 7, 0, 0]));
+        day.instructions.push(Instruction {
+            opcode: opcode("addr"),
+            a: 7,
+            b: 0,
+            c: 1,
+        });
         assert_eq!(
-            find_split_index(&[
-                "Before: [3, 2, 1, 1]",
-                "9 2 1 2",
-                "After:  [3, 2, 2, 1]",
-                "",
-                "",
-            ]),
-            3,
+            day.run_program(&table),
+            Err("instruction 2 (`addr 7 0 1`) reads a register out of range".to_string())
         );
     }
 
+    macro_rules! test_read_from_errors {
+        ($($name:ident: $values:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (lines, expected): (Vec<&str>, &str) = $values;
+                    assert_eq!(Day::read_from(&lines), Err(expected.to_string()));
+                }
+            )*
+        }
+    }
+
+    test_read_from_errors! {
+        test_read_from_errors_01: (vec![], "no samples or program found"),
+        test_read_from_errors_02: (vec![
+            "Before: [3, 2, 1, 1]",
+            "9 2 1 2",
+        ], "line 1: sample needs Before, instruction and After lines"),
+        test_read_from_errors_03: (vec![
+            "Before: [3, 2, 1]",
+            "9 2 1 2",
+            "After:  [3, 2, 2, 1]",
+        ], "line 1: expected 4 register values, found 3"),
+        test_read_from_errors_04: (vec![
+            "Before: [3, 2, 1, 1]",
+            "9 2 1 2",
+            "After: [3, 2, 2, 1]",
+        ], "line 3: expected `After:  [a, b, c, d]`, found `After: [3, 2, 2, 1]`"),
+        test_read_from_errors_05: (vec![
+            "Before: [3, 2, 1, 1]",
+            "9 2 1 4",
+            "After:  [3, 2, 2, 1]",
+        ], "line 2: register out of range in `9 2 1 4`"),
+        test_read_from_errors_06: (vec![
+            "Before: [3, 2, 1, 1]",
+            "9 2 1 2",
+            "After:  [3, 2, 2, 1]",
+            "9 2 1 2",
+        ], "line 4: expected a blank line after sample"),
+        test_read_from_errors_07: (vec![
+            "Before: [3, 2, 1, 1]",
+            "9 2 1 2",
+            "After:  [3, 2, 2, 1]",
+            "",
+            "9 2 1 2",
+        ], "line 5: expected a sample or a blank separator"),
+        test_read_from_errors_08: (vec![
+            "Before: [3, 2, 1, 1]",
+            "9 2 1 2",
+            "After:  [3, 2, 2, 1]",
+            "",
+            "",
+            "",
+            "16 2 1 2",
+        ], "line 7: unknown opcode 16"),
+        test_read_from_errors_09: (vec![
+            "Before: [3, 2, 1, 1]",
+            "9 2 1 2",
+            "After:  [3, 2, 2, 1]",
+            "",
+            "",
+            "",
+            "9 2 1 2",
+            "",
+            "9 2 1 2",
+        ], "line 8: unexpected blank line in program"),
+        test_read_from_errors_10: (vec![
+            "Before: [3, 2, 1, 1]",
+            "9 2 1",
+            "After:  [3, 2, 2, 1]",
+        ], "line 2: malformed instruction `9 2 1`"),
+    }
+
     #[test]
     fn test_parse_sample() {
         assert_eq!(
-            parse_sample(&["Before: [3, 2, 1, 1]", "9 2 1 2", "After:  [3, 2, 2, 1]"]),
-            Sample {
+            parse_sample(
+                &["Before: [3, 2, 1, 1]", "9 2 1 2", "After:  [3, 2, 2, 1]"],
+                0
+            ),
+            Ok(Sample {
                 before: [3, 2, 1, 1],
                 instruction: Instruction {
                     opcode: 9,
@@ -252,7 +527,7 @@ mod tests {
                     c: 2,
                 },
                 after: [3, 2, 2, 1],
-            },
+            }),
         );
     }
 
@@ -260,13 +535,94 @@ mod tests {
     fn test_parse_instruction() {
         assert_eq!(
             parse_instruction("9 2 1 2"),
-            Instruction {
+            Ok(Instruction {
                 opcode: 9,
                 a: 2,
                 b: 1,
                 c: 2,
-            },
+            }),
+        );
+    }
+
+    #[test]
+    fn test_opcode_table() {
+        let table = opcode_table(&crate::input::read_lines_from_input("data/day16")).unwrap();
+        assert_eq!(
+            table.mnemonics,
+            [
+                "gtrr", "borr", "gtir", "eqri", "addr", "seti", "eqrr", "gtri", "banr", "addi",
+                "setr", "mulr", "bori", "muli", "eqir", "bani",
+            ],
+        );
+        assert!(table.ambiguous.is_empty());
+        assert!(table.contradictory.is_empty());
+    }
+
+    #[test]
+    fn test_opcode_table_reports_bad_samples() {
+        let table = opcode_table(&[
+            "Before: [3, 2, 1, 1]",
+            "9 2 1 2",
+            "After:  [3, 2, 2, 1]",
+            "",
+            "Before: [0, 0, 0, 0]",
+            "9 0 0 0",
+            "After:  [7, 0, 0, 0]",
+            "",
+            "",
+            "",
+            "9 2 1 2",
+        ])
+        .unwrap();
+        assert!(["addi", "mulr", "seti"].contains(&table.mnemonics[9]));
+        assert_eq!(table.ambiguous, (0..TOTAL_INSTRUCTIONS).collect::<Vec<_>>());
+        assert_eq!(table.contradictory, vec![1]);
+    }
+
+    #[test]
+    fn test_opcode_table_bad_sample_first() {
+        let mut lines = vec![
+            "Before: [1, 2, 3, 4]".to_string(),
+            "5 2 3 0".to_string(),
+            "After:  [7, 2, 3, 4]".to_string(),
+            "".to_string(),
+        ];
+        lines.extend(crate::input::read_lines_from_input("data/day16"));
+        let table = opcode_table(&lines).unwrap();
+        assert_eq!(table.mnemonics[5], "seti");
+        assert!(table.ambiguous.is_empty());
+        assert_eq!(table.contradictory, vec![0]);
+    }
+
+    #[test]
+    fn test_propagate() {
+        let mut candidates = [0; TOTAL_INSTRUCTIONS];
+        for (i, set) in candidates.iter_mut().enumerate() {
+            *set = 1 << i;
+        }
+        candidates[0] = 0b111;
+        candidates[1] = 0b010;
+        candidates[2] = 0b110;
+        propagate(&mut candidates);
+        assert_eq!(&candidates[..3], &[0b001, 0b010, 0b100]);
+    }
+
+    #[test]
+    fn test_assign_opcodes() {
+        let mut candidates = [0; TOTAL_INSTRUCTIONS];
+        for (i, set) in candidates.iter_mut().enumerate() {
+            *set = 1 << i;
+        }
+        candidates[0] = 0b11;
+        candidates[1] = 0b11;
+        let instructions = assign_opcodes(&candidates).unwrap();
+        assert_eq!(instructions[0] + instructions[1], 1);
+        assert_eq!(
+            &instructions[2..],
+            &(2..TOTAL_INSTRUCTIONS).collect::<Vec<_>>()[..]
         );
+        candidates[2] = 0b11;
+        assert_eq!(assign_opcodes(&candidates), None);
     }
 
     macro_rules! test_run_instruction {
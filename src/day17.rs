@@ -25,7 +25,7 @@ type Location = (usize, usize);
 impl Day {
     fn read_from<T: AsRef<str>>(lines: &[T]) -> Self {
         let mut day = Day {
-            min_y: usize::max_value(),
+            min_y: usize::MAX,
             ..Day::default()
         };
         let mut xy: String;
@@ -101,7 +101,7 @@ impl Day {
     fn is_blocked(&self, x: usize, y: usize) -> bool {
         self.ground
             .get(&(x, y))
-            .map_or(false, |c| *c == '#' || *c == '~')
+            .is_some_and(|c| *c == '#' || *c == '~')
     }
 
     fn is_sand(&self, x: usize, y: usize) -> bool {
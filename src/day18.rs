@@ -1,5 +1,4 @@
 /// Day 18 (https://adventofcode.com/2018/day/18)
-
 const DELTAS: [(isize, isize); 8] = [
     (0, 1),   // north
     (1, 1),   // northeast
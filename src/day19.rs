@@ -12,24 +12,25 @@ pub fn part02<T: AsRef<str>>(lines: &[T]) -> isize {
 }
 
 #[derive(Debug, Default)]
-struct Day {
+pub struct Day {
     instructions: Vec<Instruction>,
-    register: Register,
+    pub register: Register,
     ip: usize,
+    pointer: isize,
 }
 
-type Register = [isize; 6];
+pub type Register = [isize; 6];
 
 #[derive(Debug, Default)]
-struct Instruction {
-    opcode: usize,
-    a: isize,
-    b: isize,
-    c: usize,
+pub struct Instruction {
+    pub opcode: usize,
+    pub a: isize,
+    pub b: isize,
+    pub c: usize,
 }
 
 impl Day {
-    fn read_from<T: AsRef<str>>(lines: &[T]) -> Self {
+    pub fn read_from<T: AsRef<str>>(lines: &[T]) -> Self {
         let mut iter = lines.iter();
         let mut ip;
         scan!(iter.next().expect("❌").as_ref().bytes() => "#ip {}", ip);
@@ -41,29 +42,35 @@ impl Day {
     }
 
     fn part01(&mut self) -> isize {
-        let mut ip = self.register[self.ip];
-        while ip < self.instructions.len() as isize {
-            self.register[self.ip] = ip;
-            let instruction = &self.instructions[ip as usize];
-            self.register = run_instruction(instruction, self.register);
-            ip = self.register[self.ip] + 1;
-        }
+        while self.step() {}
         self.register[0]
     }
 
     fn part02(&mut self) -> isize {
         self.register[0] = 1;
-        let mut ip = self.register[self.ip];
-        while ip < self.instructions.len() as isize && self.register[0] != 0 {
-            self.register[self.ip] = ip;
-            let instruction = &self.instructions[ip as usize];
-            self.register = run_instruction(instruction, self.register);
-            ip = self.register[self.ip] + 1;
-        }
+        while self.register[0] != 0 && self.step() {}
         let register_max = self.register.iter().max().expect("❌");
         let factors = factorize(*register_max);
         factors.iter().sum()
     }
+
+    pub fn current(&self) -> Option<&Instruction> {
+        if self.pointer < 0 {
+            return None;
+        }
+        self.instructions.get(self.pointer as usize)
+    }
+
+    pub fn step(&mut self) -> bool {
+        if self.current().is_none() {
+            return false;
+        }
+        self.register[self.ip] = self.pointer;
+        let instruction = &self.instructions[self.pointer as usize];
+        self.register = run_instruction(instruction, self.register);
+        self.pointer = self.register[self.ip] + 1;
+        true
+    }
 }
 
 fn parse_instruction(string: &str) -> Instruction {
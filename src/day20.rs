@@ -0,0 +1,153 @@
+/// Day 20 (https://adventofcode.com/2018/day/20)
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+
+const FAR_AWAY_DOORS: usize = 1000;
+
+pub fn part01<T: AsRef<str>>(lines: &[T]) -> usize {
+    Day::read_from(lines).part01()
+}
+
+pub fn part02<T: AsRef<str>>(lines: &[T]) -> usize {
+    Day::read_from(lines).part02()
+}
+
+#[derive(Debug, Default)]
+struct Day {
+    doors: HashMap<Location, HashSet<Location>>,
+}
+
+type Location = (isize, isize);
+
+impl Day {
+    fn read_from<T: AsRef<str>>(lines: &[T]) -> Self {
+        let mut day = Day::default();
+        let mut locations: HashSet<Location> = [(0, 0)].iter().copied().collect();
+        let mut groups = vec![];
+        for c in lines[0].as_ref().chars() {
+            match c {
+                'N' | 'E' | 'S' | 'W' => {
+                    let (dx, dy) = direction(c);
+                    locations = locations
+                        .into_iter()
+                        .map(|(x, y)| {
+                            day.open_door((x, y), (x + dx, y + dy));
+                            (x + dx, y + dy)
+                        })
+                        .collect();
+                }
+                '(' => groups.push((locations.clone(), HashSet::new())),
+                '|' => {
+                    let (starts, ends) = groups.last_mut().expect("❌");
+                    ends.extend(locations);
+                    locations = starts.clone();
+                }
+                ')' => {
+                    let (_, ends) = groups.pop().expect("❌");
+                    locations.extend(ends);
+                }
+                _ => {}
+            }
+        }
+        day
+    }
+
+    fn open_door(&mut self, from: Location, to: Location) {
+        self.doors.entry(from).or_default().insert(to);
+        self.doors.entry(to).or_default().insert(from);
+    }
+
+    fn part01(&self) -> usize {
+        self.distances().values().copied().max().unwrap_or(0)
+    }
+
+    fn part02(&self) -> usize {
+        self.distances()
+            .values()
+            .filter(|&&d| d >= FAR_AWAY_DOORS)
+            .count()
+    }
+
+    fn distances(&self) -> HashMap<Location, usize> {
+        let mut distances = HashMap::new();
+        let mut queue = VecDeque::new();
+        distances.insert((0, 0), 0);
+        queue.push_back((0, 0));
+        while let Some(location) = queue.pop_front() {
+            let doors = distances[&location] + 1;
+            for &next in self.doors.get(&location).into_iter().flatten() {
+                if let Entry::Vacant(entry) = distances.entry(next) {
+                    entry.insert(doors);
+                    queue.push_back(next);
+                }
+            }
+        }
+        distances
+    }
+}
+
+fn direction(c: char) -> Location {
+    match c {
+        'N' => (0, -1),
+        'E' => (1, 0),
+        'S' => (0, 1),
+        'W' => (-1, 0),
+        _ => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! test_parts {
+        ($($name:ident: $values:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (method, records, expected) = $values;
+                    assert_eq!(method(&records), expected);
+                }
+            )*
+        }
+    }
+
+    test_parts! {
+        test_part01_01: (part01, vec!["^WNE$"], 3),
+        test_part01_02: (part01, vec!["^ENWWW(NEEE|SSE(EE|N))$"], 10),
+        test_part01_03: (part01, vec!["^ENNWSWW(NEWS|)SSSEEN(WNSE|)EE(SWEN|)NNN$"], 18),
+        test_part01_04: (part01, vec!["^ESSWWN(E|NNENN(EESS(WNSE|)SSS|WWWSSSSE(SW|NNNE)))$"], 23),
+        test_part01_05: (part01, vec![
+            "^WSSEESWWWNW(S|NENNEEEENN(ESSSSW(NWSW|SSEN)|WSWWN(E|WWS(E|SS))))$"
+        ], 31),
+        test_part02_01: (part02, vec!["^ENWWW(NEEE|SSE(EE|N))$"], 0),
+        test_part02_02: (part02, vec![format!("^{}(E|W{})$", "N".repeat(998), "S".repeat(5))], 5),
+    }
+
+    #[test]
+    fn test_read_from() {
+        let day = Day::read_from(&["^EN(W|S)$"]);
+        assert_eq!(day.doors[&(0, 0)], [(1, 0)].iter().copied().collect());
+        assert_eq!(
+            day.doors[&(1, -1)],
+            [(1, 0), (0, -1)].iter().copied().collect()
+        );
+        assert_eq!(
+            day.doors[&(1, 0)],
+            [(0, 0), (1, -1)].iter().copied().collect()
+        );
+    }
+
+    #[test]
+    fn test_distances() {
+        let distances = Day::read_from(&["^N(EESW|S)E$"]).distances();
+        assert_eq!(distances[&(0, 0)], 0);
+        assert_eq!(distances[&(0, -1)], 1);
+        assert_eq!(distances[&(2, -1)], 3);
+        assert_eq!(distances[&(1, 0)], 1);
+        assert_eq!(distances[&(2, 0)], 2);
+        assert_eq!(distances.len(), 6);
+    }
+}
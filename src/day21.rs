@@ -0,0 +1,116 @@
+/// Day 21 (https://adventofcode.com/2018/day/21)
+use crate::day19::Day as Device;
+
+use std::collections::HashSet;
+
+const EQRR: usize = 15;
+
+pub fn part01<T: AsRef<str>>(lines: &[T]) -> isize {
+    Day::read_from(lines).part01()
+}
+
+pub fn part02<T: AsRef<str>>(lines: &[T]) -> isize {
+    Day::read_from(lines).part02()
+}
+
+struct Day {
+    device: Device,
+}
+
+impl Day {
+    fn read_from<T: AsRef<str>>(lines: &[T]) -> Self {
+        Day {
+            device: Device::read_from(lines),
+        }
+    }
+
+    fn part01(&mut self) -> isize {
+        self.next_halting_value().expect("❌")
+    }
+
+    fn part02(&mut self) -> isize {
+        let mut seen = HashSet::new();
+        let mut last_value = None;
+        while let Some(value) = self.next_halting_value() {
+            if !seen.insert(value) {
+                break;
+            }
+            last_value = Some(value);
+        }
+        last_value.expect("❌")
+    }
+
+    /// Runs the device until it compares some register against register 0 and returns the value
+    /// register 0 would need to hold for the program to halt right there
+    fn next_halting_value(&mut self) -> Option<isize> {
+        loop {
+            let instruction = self.device.current()?;
+            let compared = match (instruction.opcode, instruction.a, instruction.b) {
+                (EQRR, 0, other) | (EQRR, other, 0) if other != 0 => Some(other as usize),
+                _ => None,
+            };
+            if !self.device.step() {
+                return None;
+            }
+            if let Some(register) = compared {
+                return Some(self.device.register[register]);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! test_parts {
+        ($($name:ident: $values:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (method, records, expected) = $values;
+                    assert_eq!(method(&records), expected);
+                }
+            )*
+        }
+    }
+
+    // r3 walks through 3, 2, 13, 4, 7, 6, 1, 8, 11, 10, 5, 12, 15, 14, 9, 0, 3, ... and halts at 0
+    const HALTING: [&str; 8] = [
+        "#ip 5",
+        "seti 0 0 3",
+        "muli 3 5 3",
+        "addi 3 3 3",
+        "bani 3 15 3",
+        "eqrr 3 0 4",
+        "addr 4 5 5",
+        "seti 0 0 5",
+    ];
+
+    // Same walk with bit 4 set, so register 0 never matches and the sequence repeats forever
+    const LOOPING: [&str; 9] = [
+        "#ip 5",
+        "seti 0 0 3",
+        "muli 3 5 3",
+        "addi 3 3 3",
+        "bani 3 15 3",
+        "bori 3 16 3",
+        "eqrr 0 3 4",
+        "addr 4 5 5",
+        "seti 0 0 5",
+    ];
+
+    test_parts! {
+        test_part01_01: (part01, HALTING.to_vec(), 3),
+        test_part01_02: (part01, LOOPING.to_vec(), 19),
+        test_part02_01: (part02, HALTING.to_vec(), 0),
+        test_part02_02: (part02, LOOPING.to_vec(), 16),
+    }
+
+    #[test]
+    fn test_next_halting_value() {
+        let mut day = Day::read_from(&HALTING);
+        let values: Vec<_> = (0..5).map(|_| day.next_halting_value()).collect();
+        assert_eq!(values, vec![Some(3), Some(2), Some(13), Some(4), Some(7)]);
+    }
+}
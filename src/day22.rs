@@ -0,0 +1,204 @@
+/// Day 22 (https://adventofcode.com/2018/day/22)
+extern crate text_io;
+
+use text_io::scan;
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::collections::HashMap;
+
+const EROSION_MODULO: usize = 20183;
+const SWITCH_MINUTES: usize = 7;
+
+// A tool can be used in every region type but the one sharing its number, e.g. neither (0) is
+// not allowed in rocky (0) regions
+const NEITHER: Tool = 0;
+const TORCH: Tool = 1;
+const CLIMBING_GEAR: Tool = 2;
+
+pub fn part01<T: AsRef<str>>(lines: &[T]) -> usize {
+    Day::read_from(lines).part01()
+}
+
+pub fn part02<T: AsRef<str>>(lines: &[T]) -> usize {
+    Day::read_from(lines).part02()
+}
+
+#[derive(Debug, Default)]
+struct Day {
+    cave: Cave,
+}
+
+#[derive(Debug, Default)]
+struct Cave {
+    depth: usize,
+    target: Location,
+    erosion: Vec<Vec<usize>>,
+}
+
+type Location = (usize, usize);
+type Tool = usize;
+
+impl Day {
+    fn read_from<T: AsRef<str>>(lines: &[T]) -> Self {
+        let (depth, x, y): (usize, usize, usize);
+        scan!(lines[0].as_ref().bytes() => "depth: {}", depth);
+        scan!(lines[1].as_ref().bytes() => "target: {},{}", x, y);
+        Day {
+            cave: Cave {
+                depth,
+                target: (x, y),
+                ..Cave::default()
+            },
+        }
+    }
+
+    fn part01(&mut self) -> usize {
+        let (tx, ty) = self.cave.target;
+        let mut risk_level = 0;
+        for y in 0..=ty {
+            for x in 0..=tx {
+                risk_level += self.cave.region_type((x, y));
+            }
+        }
+        risk_level
+    }
+
+    fn part02(&mut self) -> usize {
+        let target = (self.cave.target, TORCH);
+        let mut minutes = HashMap::new();
+        let mut queue = BinaryHeap::new();
+        minutes.insert(((0, 0), TORCH), 0);
+        queue.push(Reverse((0, (0, 0), TORCH)));
+        while let Some(Reverse((elapsed, location, tool))) = queue.pop() {
+            if (location, tool) == target {
+                return elapsed;
+            }
+            if minutes.get(&(location, tool)).is_some_and(|&m| m < elapsed) {
+                continue;
+            }
+            let mut moves = vec![];
+            let other_tool = 3 - tool - self.cave.region_type(location);
+            moves.push((elapsed + SWITCH_MINUTES, location, other_tool));
+            for neighbor in neighbors(location) {
+                if self.cave.region_type(neighbor) != tool {
+                    moves.push((elapsed + 1, neighbor, tool));
+                }
+            }
+            for (elapsed, location, tool) in moves {
+                if minutes.get(&(location, tool)).is_none_or(|&m| elapsed < m) {
+                    minutes.insert((location, tool), elapsed);
+                    queue.push(Reverse((elapsed, location, tool)));
+                }
+            }
+        }
+        unreachable!()
+    }
+}
+
+impl Cave {
+    fn region_type(&mut self, location: Location) -> usize {
+        self.erosion_level(location) % 3
+    }
+
+    fn erosion_level(&mut self, (x, y): Location) -> usize {
+        self.grow(x + 1, y + 1);
+        self.erosion[y][x]
+    }
+
+    /// Extends the erosion level cache so it covers at least `width` by `height` regions
+    fn grow(&mut self, width: usize, height: usize) {
+        let old_width = self.erosion.first().map_or(0, |row| row.len());
+        if width <= old_width && height <= self.erosion.len() {
+            return;
+        }
+        let width = width.max(old_width);
+        for y in 0..height.max(self.erosion.len()) {
+            if y == self.erosion.len() {
+                self.erosion.push(Vec::with_capacity(width));
+            }
+            for x in self.erosion[y].len()..width {
+                let geologic_index = match (x, y) {
+                    (0, 0) => 0,
+                    location if location == self.target => 0,
+                    (x, 0) => x * 16807,
+                    (0, y) => y * 48271,
+                    (x, y) => self.erosion[y][x - 1] * self.erosion[y - 1][x],
+                };
+                let erosion_level = (geologic_index + self.depth) % EROSION_MODULO;
+                self.erosion[y].push(erosion_level);
+            }
+        }
+    }
+}
+
+fn neighbors((x, y): Location) -> Vec<Location> {
+    let mut neighbors = vec![(x + 1, y), (x, y + 1)];
+    if x > 0 {
+        neighbors.push((x - 1, y));
+    }
+    if y > 0 {
+        neighbors.push((x, y - 1));
+    }
+    neighbors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! test_parts {
+        ($($name:ident: $values:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (method, records, expected) = $values;
+                    assert_eq!(method(&records), expected);
+                }
+            )*
+        }
+    }
+
+    test_parts! {
+        test_part01_01: (part01, vec!["depth: 510", "target: 10,10"], 114),
+        test_part02_01: (part02, vec!["depth: 510", "target: 10,10"], 45),
+    }
+
+    #[test]
+    fn test_erosion_level() {
+        let mut day = Day::read_from(&["depth: 510", "target: 10,10"]);
+        assert_eq!(day.cave.erosion_level((0, 0)), 510);
+        assert_eq!(day.cave.erosion_level((1, 0)), 17317);
+        assert_eq!(day.cave.erosion_level((0, 1)), 8415);
+        assert_eq!(day.cave.erosion_level((1, 1)), 1805);
+        assert_eq!(day.cave.erosion_level((10, 10)), 510);
+    }
+
+    #[test]
+    fn test_region_type() {
+        let mut day = Day::read_from(&["depth: 510", "target: 10,10"]);
+        assert_eq!(day.cave.region_type((0, 0)), 0);
+        assert_eq!(day.cave.region_type((1, 0)), 1);
+        assert_eq!(day.cave.region_type((0, 1)), 0);
+        assert_eq!(day.cave.region_type((1, 1)), 2);
+        assert_eq!(day.cave.region_type((10, 10)), 0);
+    }
+
+    #[test]
+    fn test_grow() {
+        let mut cave = Cave {
+            depth: 510,
+            target: (10, 10),
+            ..Cave::default()
+        };
+        cave.grow(2, 3);
+        assert_eq!(
+            cave.erosion,
+            vec![vec![510, 17317], vec![8415, 1805], vec![16320, 11113]]
+        );
+        cave.grow(3, 1);
+        assert_eq!(cave.erosion.len(), 3);
+        assert!(cave.erosion.iter().all(|row| row.len() == 3));
+        assert_eq!(cave.erosion_level((1, 1)), 1805);
+    }
+}
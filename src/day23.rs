@@ -0,0 +1,227 @@
+/// Day 23 (https://adventofcode.com/2018/day/23)
+extern crate text_io;
+
+use text_io::scan;
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+pub fn part01<T: AsRef<str>>(lines: &[T]) -> usize {
+    Day::read_from(lines).part01()
+}
+
+pub fn part02<T: AsRef<str>>(lines: &[T]) -> i64 {
+    Day::read_from(lines).part02()
+}
+
+#[derive(Debug, Default)]
+struct Day {
+    nanobots: Vec<Nanobot>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Nanobot {
+    pos: Point,
+    r: i64,
+}
+
+type Point = [i64; 3];
+
+/// Cube of `size` points along each axis starting at `min`
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+struct Cube {
+    min: Point,
+    size: i64,
+}
+
+impl Day {
+    fn read_from<T: AsRef<str>>(lines: &[T]) -> Self {
+        Day {
+            nanobots: lines.iter().map(|l| parse_nanobot(l.as_ref())).collect(),
+        }
+    }
+
+    fn part01(&self) -> usize {
+        let strongest = self.nanobots.iter().max_by_key(|n| n.r).expect("❌");
+        self.nanobots
+            .iter()
+            .filter(|n| distance(&strongest.pos, &n.pos) <= strongest.r)
+            .count()
+    }
+
+    /// Searches the space by splitting cubes in eight, always refining the cube that reaches the
+    /// most nanobots and, among those, the one closest to the origin
+    fn part02(&self) -> i64 {
+        let mut queue = BinaryHeap::new();
+        queue.push(self.rank(self.bounding_cube()));
+        while let Some((_, Reverse(dist), cube)) = queue.pop() {
+            if cube.size == 1 {
+                return dist;
+            }
+            for child in cube.split() {
+                queue.push(self.rank(child));
+            }
+        }
+        unreachable!()
+    }
+
+    fn rank(&self, cube: Cube) -> (usize, Reverse<i64>, Cube) {
+        (self.in_range(&cube), Reverse(cube.distance()), cube)
+    }
+
+    fn bounding_cube(&self) -> Cube {
+        let mut min = [0; 3];
+        let mut max = [0; 3];
+        for nanobot in &self.nanobots {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(nanobot.pos[axis] - nanobot.r);
+                max[axis] = max[axis].max(nanobot.pos[axis] + nanobot.r);
+            }
+        }
+        let span = (0..3)
+            .map(|axis| max[axis] - min[axis] + 1)
+            .max()
+            .expect("❌");
+        let mut size = 1;
+        while size < span {
+            size *= 2;
+        }
+        Cube { min, size }
+    }
+
+    fn in_range(&self, cube: &Cube) -> usize {
+        self.nanobots
+            .iter()
+            .filter(|n| cube.distance_to(&n.pos) <= n.r)
+            .count()
+    }
+}
+
+impl Cube {
+    fn split(&self) -> Vec<Cube> {
+        let size = self.size / 2;
+        let mut cubes = Vec::with_capacity(8);
+        for &dx in &[0, size] {
+            for &dy in &[0, size] {
+                for &dz in &[0, size] {
+                    cubes.push(Cube {
+                        min: [self.min[0] + dx, self.min[1] + dy, self.min[2] + dz],
+                        size,
+                    });
+                }
+            }
+        }
+        cubes
+    }
+
+    fn distance(&self) -> i64 {
+        self.distance_to(&[0, 0, 0])
+    }
+
+    fn distance_to(&self, point: &Point) -> i64 {
+        let mut dist = 0;
+        for (&lo, &p) in self.min.iter().zip(point) {
+            let hi = lo + self.size - 1;
+            if p < lo {
+                dist += lo - p;
+            } else if p > hi {
+                dist += p - hi;
+            }
+        }
+        dist
+    }
+}
+
+fn distance(a: &Point, b: &Point) -> i64 {
+    a.iter().zip(b).map(|(a, b)| (a - b).abs()).sum()
+}
+
+fn parse_nanobot(line: &str) -> Nanobot {
+    let mut n = Nanobot::default();
+    scan!(line.bytes() => "pos=<{},{},{}>, r={}", n.pos[0], n.pos[1], n.pos[2], n.r);
+    n
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! test_parts {
+        ($($name:ident: $values:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (method, records, expected) = $values;
+                    assert_eq!(method(&records), expected);
+                }
+            )*
+        }
+    }
+
+    test_parts! {
+        test_part01_01: (part01, vec![
+            "pos=<0,0,0>, r=4",
+            "pos=<1,0,0>, r=1",
+            "pos=<4,0,0>, r=3",
+            "pos=<0,2,0>, r=1",
+            "pos=<0,5,0>, r=3",
+            "pos=<0,0,3>, r=1",
+            "pos=<1,1,1>, r=1",
+            "pos=<1,1,2>, r=1",
+            "pos=<1,3,1>, r=1",
+        ], 7),
+        test_part02_01: (part02, vec![
+            "pos=<10,12,12>, r=2",
+            "pos=<12,14,12>, r=2",
+            "pos=<16,12,12>, r=4",
+            "pos=<14,14,14>, r=6",
+            "pos=<50,50,50>, r=200",
+            "pos=<10,10,10>, r=5",
+        ], 36),
+        test_part02_02: (part02, vec![
+            "pos=<-10,-12,-12>, r=2",
+            "pos=<-12,-14,-12>, r=2",
+            "pos=<-16,-12,-12>, r=4",
+            "pos=<-14,-14,-14>, r=6",
+            "pos=<-50,-50,-50>, r=200",
+            "pos=<-10,-10,-10>, r=5",
+        ], 36),
+    }
+
+    #[test]
+    fn test_parse_nanobot() {
+        assert_eq!(
+            parse_nanobot("pos=<-1,20,3>, r=4"),
+            Nanobot {
+                pos: [-1, 20, 3],
+                r: 4,
+            },
+        );
+    }
+
+    #[test]
+    fn test_cube_distance_to() {
+        let cube = Cube {
+            min: [-2, 0, 3],
+            size: 4,
+        };
+        assert_eq!(cube.distance_to(&[0, 1, 4]), 0);
+        assert_eq!(cube.distance_to(&[0, 0, 0]), 3);
+        assert_eq!(cube.distance_to(&[-5, 10, 7]), 3 + 7 + 1);
+    }
+
+    #[test]
+    fn test_cube_split() {
+        let cube = Cube {
+            min: [0, 0, 0],
+            size: 2,
+        };
+        let cubes = cube.split();
+        assert_eq!(cubes.len(), 8);
+        assert!(cubes.iter().all(|c| c.size == 1));
+        assert!(cubes.contains(&Cube {
+            min: [1, 0, 1],
+            size: 1,
+        }));
+    }
+}
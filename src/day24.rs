@@ -0,0 +1,288 @@
+/// Day 24 (https://adventofcode.com/2018/day/24)
+extern crate text_io;
+
+use text_io::scan;
+
+use std::cmp::Reverse;
+use std::collections::HashSet;
+
+pub fn part01<T: AsRef<str>>(lines: &[T]) -> usize {
+    Day::read_from(lines).part01()
+}
+
+pub fn part02<T: AsRef<str>>(lines: &[T]) -> usize {
+    Day::read_from(lines).part02()
+}
+
+#[derive(Debug, Default)]
+struct Day {
+    battle: Battle,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Battle {
+    groups: Vec<Group>,
+    boost: usize,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Group {
+    army: Army,
+    units: usize,
+    hit_points: usize,
+    weaknesses: Vec<String>,
+    immunities: Vec<String>,
+    attack_damage: usize,
+    attack_type: String,
+    initiative: usize,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum Army {
+    #[default]
+    ImmuneSystem,
+    Infection,
+}
+
+impl Day {
+    fn read_from<T: AsRef<str>>(lines: &[T]) -> Self {
+        let mut day = Day::default();
+        let mut army = Army::ImmuneSystem;
+        for line in lines.iter().map(|l| l.as_ref()) {
+            match line {
+                "Immune System:" => army = Army::ImmuneSystem,
+                "Infection:" => army = Army::Infection,
+                "" => {}
+                _ => day.battle.groups.push(parse_group(line, army)),
+            }
+        }
+        day
+    }
+
+    fn part01(&self) -> usize {
+        let mut battle = self.battle.clone();
+        battle.fight().expect("❌").1
+    }
+
+    /// Tries every boost in turn, as a boost that wins can be followed by a stalemate or a loss
+    fn part02(&self) -> usize {
+        (1..)
+            .find_map(|boost| self.immune_system_units(boost))
+            .expect("❌")
+    }
+
+    /// Units the immune system is left with when it wins with the given boost
+    fn immune_system_units(&self, boost: usize) -> Option<usize> {
+        let mut battle = self.battle.clone();
+        battle.boost = boost;
+        match battle.fight() {
+            Some((Army::ImmuneSystem, units)) => Some(units),
+            _ => None,
+        }
+    }
+}
+
+impl Battle {
+    /// Fights until one army is left, returning it with its remaining units, or `None` when the
+    /// armies reach a stalemate in which no unit dies anymore
+    fn fight(&mut self) -> Option<(Army, usize)> {
+        loop {
+            let army = self.groups.first()?.army;
+            if self.groups.iter().all(|g| g.army == army) {
+                return Some((army, self.groups.iter().map(|g| g.units).sum()));
+            }
+            let targets = self.select_targets();
+            if self.attack(&targets) == 0 {
+                return None;
+            }
+            self.groups.retain(|g| g.units > 0);
+        }
+    }
+
+    fn select_targets(&self) -> Vec<Option<usize>> {
+        let mut order: Vec<_> = (0..self.groups.len()).collect();
+        order.sort_by_key(|&i| {
+            let group = &self.groups[i];
+            Reverse((self.effective_power(group), group.initiative))
+        });
+        let mut targets = vec![None; self.groups.len()];
+        let mut chosen = HashSet::new();
+        for attacker in order {
+            let group = &self.groups[attacker];
+            targets[attacker] = (0..self.groups.len())
+                .filter(|&i| self.groups[i].army != group.army && !chosen.contains(&i))
+                .map(|i| (self.damage(group, &self.groups[i]), i))
+                .filter(|&(damage, _)| damage > 0)
+                .max_by_key(|&(damage, i)| {
+                    let defender = &self.groups[i];
+                    (damage, self.effective_power(defender), defender.initiative)
+                })
+                .map(|(_, i)| i);
+            if let Some(defender) = targets[attacker] {
+                chosen.insert(defender);
+            }
+        }
+        targets
+    }
+
+    fn attack(&mut self, targets: &[Option<usize>]) -> usize {
+        let mut order: Vec<_> = (0..self.groups.len()).collect();
+        order.sort_by_key(|&i| Reverse(self.groups[i].initiative));
+        let mut total_killed = 0;
+        for attacker in order {
+            if let Some(defender) = targets[attacker] {
+                if self.groups[attacker].units == 0 {
+                    continue;
+                }
+                let damage = self.damage(&self.groups[attacker], &self.groups[defender]);
+                let defender = &mut self.groups[defender];
+                let killed = defender.units.min(damage / defender.hit_points);
+                defender.units -= killed;
+                total_killed += killed;
+            }
+        }
+        total_killed
+    }
+
+    fn effective_power(&self, group: &Group) -> usize {
+        let mut attack_damage = group.attack_damage;
+        if group.army == Army::ImmuneSystem {
+            attack_damage += self.boost;
+        }
+        group.units * attack_damage
+    }
+
+    fn damage(&self, attacker: &Group, defender: &Group) -> usize {
+        if defender.immunities.contains(&attacker.attack_type) {
+            0
+        } else if defender.weaknesses.contains(&attacker.attack_type) {
+            2 * self.effective_power(attacker)
+        } else {
+            self.effective_power(attacker)
+        }
+    }
+}
+
+fn parse_group(line: &str, army: Army) -> Group {
+    let mut g = Group {
+        army,
+        ..Group::default()
+    };
+    let mut line = line.to_string();
+    if let (Some(open), Some(close)) = (line.find('('), line.find(')')) {
+        for modifiers in line[open + 1..close].split("; ") {
+            if let Some(types) = modifiers.strip_prefix("weak to ") {
+                g.weaknesses = types.split(", ").map(|t| t.to_string()).collect();
+            } else if let Some(types) = modifiers.strip_prefix("immune to ") {
+                g.immunities = types.split(", ").map(|t| t.to_string()).collect();
+            }
+        }
+        line.replace_range(open..=close + 1, "");
+    }
+    scan!(line.bytes() => "{} units each with {} hit points with an attack that does {} {} damage at initiative {}",
+        g.units, g.hit_points, g.attack_damage, g.attack_type, g.initiative);
+    g
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: [&str; 7] = [
+        "Immune System:",
+        "17 units each with 5390 hit points (weak to radiation, bludgeoning) with an attack that does 4507 fire damage at initiative 2",
+        "989 units each with 1274 hit points (immune to fire; weak to bludgeoning, slashing) with an attack that does 25 slashing damage at initiative 3",
+        "",
+        "Infection:",
+        "801 units each with 4706 hit points (weak to radiation) with an attack that does 116 bludgeoning damage at initiative 1",
+        "4485 units each with 2961 hit points (immune to radiation; weak to fire, cold) with an attack that does 12 slashing damage at initiative 4",
+    ];
+
+    macro_rules! test_parts {
+        ($($name:ident: $values:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (method, records, expected) = $values;
+                    assert_eq!(method(&records), expected);
+                }
+            )*
+        }
+    }
+
+    test_parts! {
+        test_part01_01: (part01, EXAMPLE.to_vec(), 5216),
+        test_part02_01: (part02, EXAMPLE.to_vec(), 51),
+    }
+
+    #[test]
+    fn test_parse_group() {
+        assert_eq!(
+            parse_group(EXAMPLE[2], Army::ImmuneSystem),
+            Group {
+                army: Army::ImmuneSystem,
+                units: 989,
+                hit_points: 1274,
+                weaknesses: vec!["bludgeoning".to_string(), "slashing".to_string()],
+                immunities: vec!["fire".to_string()],
+                attack_damage: 25,
+                attack_type: "slashing".to_string(),
+                initiative: 3,
+            },
+        );
+        assert_eq!(
+            parse_group(
+                "12 units each with 3 hit points with an attack that does 4 cold damage at initiative 5",
+                Army::Infection,
+            ),
+            Group {
+                army: Army::Infection,
+                units: 12,
+                hit_points: 3,
+                attack_damage: 4,
+                attack_type: "cold".to_string(),
+                initiative: 5,
+                ..Group::default()
+            },
+        );
+    }
+
+    #[test]
+    fn test_fight_with_boost() {
+        let mut battle = Day::read_from(&EXAMPLE).battle;
+        battle.boost = 1570;
+        assert_eq!(battle.fight(), Some((Army::ImmuneSystem, 51)));
+    }
+
+    #[test]
+    fn test_part02_stalemate_above_win() {
+        let day = Day::read_from(&[
+            "Immune System:",
+            "3 units each with 19 hit points (weak to cold) with an attack that does 8 fire damage at initiative 3",
+            "14 units each with 3 hit points (immune to cold) with an attack that does 8 cold damage at initiative 4",
+            "",
+            "Infection:",
+            "4 units each with 4 hit points (immune to fire; weak to cold) with an attack that does 5 cold damage at initiative 2",
+            "14 units each with 26 hit points (weak to cold) with an attack that does 9 fire damage at initiative 1",
+        ]);
+        assert_eq!(day.immune_system_units(3), Some(5));
+        let mut battle = day.battle.clone();
+        battle.boost = 4;
+        assert_eq!(battle.fight(), None);
+        assert_eq!(day.immune_system_units(5), Some(15));
+        assert_eq!(day.part02(), 5);
+    }
+
+    #[test]
+    fn test_fight_stalemate() {
+        let mut battle = Day::read_from(&[
+            "Immune System:",
+            "10 units each with 20 hit points (immune to fire) with an attack that does 1 cold damage at initiative 2",
+            "",
+            "Infection:",
+            "10 units each with 20 hit points (immune to fire) with an attack that does 1 cold damage at initiative 1",
+        ])
+        .battle;
+        assert_eq!(battle.fight(), None);
+    }
+}
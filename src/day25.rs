@@ -0,0 +1,129 @@
+/// Day 25 (https://adventofcode.com/2018/day/25)
+extern crate text_io;
+
+use crate::disjoint_set::DisjointSet;
+
+use text_io::scan;
+
+const CONSTELLATION_DISTANCE: isize = 3;
+
+pub fn part01<T: AsRef<str>>(lines: &[T]) -> usize {
+    Day::read_from(lines).part01()
+}
+
+pub fn part02<T: AsRef<str>>(_lines: &[T]) -> String {
+    String::from("🎄")
+}
+
+#[derive(Debug, Default)]
+struct Day {
+    points: Vec<Point>,
+}
+
+type Point = [isize; 4];
+
+impl Day {
+    fn read_from<T: AsRef<str>>(lines: &[T]) -> Self {
+        Day {
+            points: lines
+                .iter()
+                .filter(|l| !l.as_ref().is_empty())
+                .map(|l| parse_point(l.as_ref()))
+                .collect(),
+        }
+    }
+
+    fn part01(&self) -> usize {
+        let mut constellations = DisjointSet::new();
+        for (i, a) in self.points.iter().enumerate() {
+            constellations.insert(i);
+            for (j, b) in self.points.iter().enumerate().skip(i + 1) {
+                if distance(a, b) <= CONSTELLATION_DISTANCE {
+                    constellations.union(i, j);
+                }
+            }
+        }
+        constellations.sets()
+    }
+}
+
+fn distance(a: &Point, b: &Point) -> isize {
+    a.iter().zip(b).map(|(a, b)| (a - b).abs()).sum()
+}
+
+fn parse_point(line: &str) -> Point {
+    let mut p = Point::default();
+    scan!(line.trim().bytes() => "{},{},{},{}", p[0], p[1], p[2], p[3]);
+    p
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! test_parts {
+        ($($name:ident: $values:expr,)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let (method, records, expected) = $values;
+                    assert_eq!(method(&records), expected);
+                }
+            )*
+        }
+    }
+
+    test_parts! {
+        test_part01_01: (part01, vec![
+            "0,0,0,0",
+            "3,0,0,0",
+            "0,3,0,0",
+            "0,0,3,0",
+            "0,0,0,3",
+            "0,0,0,6",
+            "9,0,0,0",
+            "12,0,0,0",
+        ], 2),
+        test_part01_02: (part01, vec![
+            "-1,2,2,0",
+            "0,0,2,-2",
+            "0,0,0,-2",
+            "-1,2,0,0",
+            "-2,-2,-2,2",
+            "3,0,2,-1",
+            "-1,3,2,2",
+            "-1,0,-1,0",
+            "0,2,1,-2",
+            "3,0,0,0",
+        ], 4),
+        test_part01_03: (part01, vec![
+            "1,-1,0,1",
+            "2,0,-1,0",
+            "3,2,-1,0",
+            "0,0,3,1",
+            "0,0,-1,-1",
+            "2,3,-2,0",
+            "-2,2,0,0",
+            "2,-2,0,-1",
+            "1,-1,0,-1",
+            "3,2,0,2",
+        ], 3),
+        test_part01_04: (part01, vec![
+            "1,-1,-1,-2",
+            "-2,-2,0,1",
+            "0,2,1,3",
+            "-2,3,-2,1",
+            "0,2,3,-2",
+            "-1,-1,1,-2",
+            "0,-2,-1,0",
+            "-2,2,3,-1",
+            "1,2,2,0",
+            "-1,-2,0,-2",
+        ], 8),
+    }
+
+    #[test]
+    fn test_parse_point() {
+        assert_eq!(parse_point(" -1,2,-3,40"), [-1, 2, -3, 40]);
+    }
+}
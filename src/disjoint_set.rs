@@ -0,0 +1,122 @@
+/// Disjoint-set forest (union-find) over arbitrary hashable elements
+use std::collections::HashMap;
+use std::hash::Hash;
+
+#[derive(Debug, Default)]
+pub struct DisjointSet<T> {
+    indexes: HashMap<T, usize>,
+    parents: Vec<usize>,
+    ranks: Vec<usize>,
+    sets: usize,
+}
+
+impl<T: Eq + Hash> DisjointSet<T> {
+    pub fn new() -> Self {
+        DisjointSet {
+            indexes: HashMap::new(),
+            parents: vec![],
+            ranks: vec![],
+            sets: 0,
+        }
+    }
+
+    /// Adds `element` in a set of its own, unless it's already known
+    pub fn insert(&mut self, element: T) -> usize {
+        let next = self.parents.len();
+        let index = *self.indexes.entry(element).or_insert(next);
+        if index == next {
+            self.parents.push(next);
+            self.ranks.push(0);
+            self.sets += 1;
+        }
+        index
+    }
+
+    /// Representative index of the set containing `element`
+    pub fn find(&mut self, element: &T) -> Option<usize> {
+        let index = *self.indexes.get(element)?;
+        Some(self.root(index))
+    }
+
+    /// Merges the sets containing `a` and `b`, inserting them if needed, and tells whether they
+    /// were apart before
+    pub fn union(&mut self, a: T, b: T) -> bool {
+        let (a, b) = (self.insert(a), self.insert(b));
+        let (a, b) = (self.root(a), self.root(b));
+        if a == b {
+            return false;
+        }
+        let (lo, hi) = if self.ranks[a] < self.ranks[b] {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        self.parents[lo] = hi;
+        if self.ranks[lo] == self.ranks[hi] {
+            self.ranks[hi] += 1;
+        }
+        self.sets -= 1;
+        true
+    }
+
+    pub fn same_set(&mut self, a: &T, b: &T) -> bool {
+        match (self.find(a), self.find(b)) {
+            (Some(a), Some(b)) => a == b,
+            _ => false,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.parents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parents.is_empty()
+    }
+
+    pub fn sets(&self) -> usize {
+        self.sets
+    }
+
+    fn root(&mut self, mut index: usize) -> usize {
+        let mut root = index;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+        while self.parents[index] != root {
+            let parent = self.parents[index];
+            self.parents[index] = root;
+            index = parent;
+        }
+        root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert() {
+        let mut set = DisjointSet::new();
+        assert_eq!(set.insert('a'), 0);
+        assert_eq!(set.insert('b'), 1);
+        assert_eq!(set.insert('a'), 0);
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.sets(), 2);
+    }
+
+    #[test]
+    fn test_union() {
+        let mut set = DisjointSet::new();
+        assert!(set.union(1, 2));
+        assert!(set.union(3, 4));
+        assert!(!set.union(2, 1));
+        assert!(set.same_set(&1, &2));
+        assert!(!set.same_set(&1, &3));
+        assert!(set.union(4, 1));
+        assert!(set.same_set(&2, &3));
+        assert_eq!(set.sets(), 1);
+        assert_eq!(set.find(&5), None);
+    }
+}
@@ -14,6 +14,11 @@ pub fn read_lines_from_input(path: &str) -> Vec<String> {
     data.lines().map(|l| l.to_string()).collect()
 }
 
+/// Prefixes `error` with the 1-based number of the line at `index`
+pub fn at_line<E: std::fmt::Display>(index: usize, error: E) -> String {
+    format!("line {}: {}", index + 1, error)
+}
+
 pub fn string_to_i32(string: &str) -> i32 {
     string.parse::<i32>().expect("failed parsing i32")
 }
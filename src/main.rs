@@ -1,12 +1,12 @@
 #![allow(clippy::zero_prefixed_literal)]
 #![allow(unused)]
-#![feature(binary_heap_retain)]
 extern crate clipboard;
 
 use clipboard::ClipboardContext;
 use clipboard::ClipboardProvider;
 
 use std::env;
+use std::path::PathBuf;
 
 mod day01;
 mod day02;
@@ -27,15 +27,53 @@ mod day16;
 mod day17;
 mod day18;
 mod day19;
+mod day20;
+mod day21;
+mod day22;
+mod day23;
+mod day24;
+mod day25;
 
+mod disjoint_set;
 mod input;
 
 fn main() {
-    let day_part = env::args().nth(1).expect("missing <day>.<part> (e.g. 1.1)");
-    solve_day(&day_part);
+    let args: Vec<String> = env::args().collect();
+    let day_part = args.get(1).expect("missing <day>.<part> (e.g. 1.1)");
+    // Flags followed by an optional value, e.g. `--replay 10` or `--replay`
+    let flag = |name: &str| {
+        let index = args.iter().position(|a| a == name)?;
+        Some(args.get(index + 1).filter(|a| !a.starts_with("--")))
+    };
+    if let Some(until) = flag("--replay") {
+        replay(day_part, until, args.contains(&"--ansi".into()));
+    } else {
+        let cache_dir =
+            flag("--cache").map(|dir| dir.map_or_else(day14::default_cache_dir, PathBuf::from));
+        solve_day(day_part, cache_dir);
+    }
 }
 
-fn solve_day(day_part: &str) {
+/// Prints the rounds of the day 15 combat, stopping after round `until` if given
+fn replay(day_part: &str, until: Option<&String>, ansi: bool) {
+    let lines = input::read_lines();
+    let elf_attack_power = match day_part {
+        "15.1" => 3,
+        "15.2" => day15::winning_elf_attack_power(&lines),
+        _ => panic!("🤷 only 15.1 and 15.2 can be replayed"),
+    };
+    let options = day15::ReplayOptions {
+        until: until.map(|u| u.parse().expect("invalid round to replay until")),
+        ansi,
+        elf_attack_power,
+    };
+    for frame in day15::replay(&lines, &options) {
+        println!("{}", frame);
+    }
+}
+
+/// Solves the puzzle, reading the day 14 scores from the cache in `cache_dir` when given
+fn solve_day(day_part: &str, cache_dir: Option<PathBuf>) {
     let lines = input::read_lines();
     macro_rules! get_answer {($($day:tt)*) => (::paste::paste! {
         match day_part.replace(".", "").parse::<i32>() {
@@ -46,7 +84,15 @@ fn solve_day(day_part: &str) {
             _ => panic!("🤷"),
         }
     })}
-    let answer = get_answer!(01 02 03 04 05 06 07 08 09 10 11 12 13 14 15 16 17 18 19);
+    let answer = match (day_part, cache_dir) {
+        ("14.1", Some(dir)) => day14::part01_cached(&lines, &dir).expect("failed caching scores"),
+        ("14.2", Some(dir)) => day14::part02_cached(&lines, &dir)
+            .expect("failed caching scores")
+            .to_string(),
+        _ => {
+            get_answer!(01 02 03 04 05 06 07 08 09 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25)
+        }
+    };
     copy_to_clipboard(&answer);
     println!("Your answer is: {} (already copied to clipboard)", answer)
 }